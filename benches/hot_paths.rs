@@ -0,0 +1,250 @@
+// Benchmarks of the calls the control loop makes every tick, over a few representative arenas.
+//
+//     cargo bench                                  every benchmark
+//     cargo bench -- greedy                        the ones whose name contains "greedy"
+//     cargo bench -- --save-baseline main          keep the results as baseline "main"
+//     cargo bench -- --baseline main               compare with "main", fails on regressions
+//     cargo bench --features large-grid            the same on a 15 x 15 arena
+//
+// Every call is reported in nanoseconds, in cycles of the time stamp counter on x86_64 and as a
+// share of the 5 ms the robot has per tick. Without `--bench`, as under `cargo test`, every
+// benchmark only runs once to check that it still works.
+
+use std::{collections::BTreeMap, env, fs, hint::black_box, num::NonZero, path::PathBuf, process, time::{Duration, Instant}};
+
+use threat_map::{
+    borders::Borders,
+    budget::NodeBudget,
+    coordinates::Coordinate,
+    enemy_position::{EnemyPosition, EnemyPositions},
+    enemy_position_prediction::EnemyPositionPrediction,
+    greedy_next_move::greedy_next_move,
+    lookahead_next_move::lookahead_next_move,
+    mcts::{mcts_next_move, MctsConfig},
+    orientation::Orientation,
+    rng::Rng,
+    robot_position::RobotPosition,
+    threat_map::ThreatMap,
+    MAX_NUM_ENEMIES, N,
+};
+
+const TICK_BUDGET: Duration = Duration::from_millis(5);
+const SAMPLES: usize = 30;
+const SAMPLE_TIME: Duration = Duration::from_millis(5);
+const WARM_UP_TIME: Duration = Duration::from_millis(100);
+
+#[cfg(target_arch = "x86_64")]
+fn cycles() -> Option<u64> {
+    // SAFETY: the time stamp counter is available on every x86_64 processor
+    #[allow(unused_unsafe)]
+    Some(unsafe { core::arch::x86_64::_rdtsc() })
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn cycles() -> Option<u64> {
+    None
+}
+
+struct Measurement {
+    nanos: f64,
+    cycles: Option<f64>,
+}
+
+struct Harness {
+    full: bool,
+    filter: Option<String>,
+    save_baseline: Option<String>,
+    baseline: Option<BTreeMap<String, f64>>,
+    // regressions beyond this many percent fail the run
+    threshold: f64,
+    results: Vec<(String, f64)>,
+    regressions: usize,
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("bench-baselines").join(format!("{name}.txt"))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("hot_paths: {message}");
+    process::exit(2)
+}
+
+impl Harness {
+    fn from_args() -> Self {
+        let mut harness = Harness { full: false, filter: None, save_baseline: None, baseline: None, threshold: 10.0, results: Vec::new(), regressions: 0 };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bench" => harness.full = true,
+                "--save-baseline" => harness.save_baseline = Some(args.next().unwrap_or_else(|| fail("--save-baseline needs a name"))),
+                "--baseline" => {
+                    let name = args.next().unwrap_or_else(|| fail("--baseline needs a name"));
+                    let text = fs::read_to_string(baseline_path(&name)).unwrap_or_else(|e| fail(&format!("cannot read baseline {name}: {e}")));
+                    let entries = text.lines().filter_map(|l| l.rsplit_once(' ')).filter_map(|(n, v)| Some((n.to_string(), v.parse().ok()?)));
+                    harness.baseline = Some(entries.collect());
+                }
+                "--threshold" => harness.threshold = args.next().and_then(|t| t.parse().ok()).unwrap_or_else(|| fail("--threshold needs a percentage")),
+                // flags cargo passes on to every harness
+                _ if arg.starts_with('-') => {}
+                _ => harness.filter = Some(arg),
+            }
+        }
+        harness
+    }
+
+    fn bench<T, F: FnMut() -> T>(&mut self, name: &str, mut f: F) {
+        if self.filter.as_ref().is_some_and(|filter| !name.contains(filter.as_str())) {
+            return;
+        }
+        if !self.full {
+            black_box(f());
+            println!("{name} ... ok");
+            return;
+        }
+
+        let start = Instant::now();
+        let mut warm_up_calls = 0u64;
+        while start.elapsed() < WARM_UP_TIME {
+            black_box(f());
+            warm_up_calls += 1;
+        }
+        let calls_per_sample = ((SAMPLE_TIME.as_nanos() as u64 * warm_up_calls) / WARM_UP_TIME.as_nanos() as u64).max(1);
+
+        let mut samples: Vec<Measurement> = (0..SAMPLES).map(|_| {
+            let start = Instant::now();
+            let start_cycles = cycles();
+            for _ in 0..calls_per_sample {
+                black_box(f());
+            }
+            let elapsed = start.elapsed();
+            let cycles = cycles().zip(start_cycles).map(|(end, start)| (end - start) as f64 / calls_per_sample as f64);
+            Measurement { nanos: elapsed.as_nanos() as f64 / calls_per_sample as f64, cycles }
+        }).collect();
+        samples.sort_by(|a, b| a.nanos.total_cmp(&b.nanos));
+        let median = &samples[SAMPLES / 2];
+
+        let share = 100.0 * median.nanos / TICK_BUDGET.as_nanos() as f64;
+        let cycles = median.cycles.map_or("-".to_string(), |c| format!("{c:.0}"));
+        let mut line = format!("{name:<52} {:>12.0} ns {cycles:>12} cycles {share:>8.3}% of a tick", median.nanos);
+        if let Some(previous) = self.baseline.as_ref().and_then(|b| b.get(name)) {
+            let change = 100.0 * (median.nanos - previous) / previous;
+            line += &format!("  {change:+.1}%");
+            if change > self.threshold {
+                line += " REGRESSED";
+                self.regressions += 1;
+            }
+        }
+        println!("{line}");
+        self.results.push((name.to_string(), median.nanos));
+    }
+
+    fn finish(self) {
+        if let Some(name) = &self.save_baseline {
+            let path = baseline_path(name);
+            let text: String = self.results.iter().map(|(n, v)| format!("{n} {v}\n")).collect();
+            fs::create_dir_all(path.parent().unwrap()).and_then(|_| fs::write(&path, text)).unwrap_or_else(|e| fail(&format!("cannot write {}: {e}", path.display())));
+            println!("saved baseline {name}");
+        }
+        if self.regressions > 0 {
+            fail(&format!("{} benchmarks regressed by more than {}%", self.regressions, self.threshold));
+        }
+    }
+}
+
+struct Scenario {
+    name: &'static str,
+    robot_position: RobotPosition,
+    current: EnemyPositions,
+    previous: EnemyPositions,
+    borders: Borders,
+}
+
+const HALF: i8 = (N / 2) as i8;
+const STEPS: [Coordinate; 4] = [Coordinate { x: 0, y: -1 }, Coordinate { x: 1, y: 0 }, Coordinate { x: 0, y: 1 }, Coordinate { x: -1, y: 0 }];
+
+impl Scenario {
+    // `enemy_count` enemies on free cells away from the robot in the centre, each one step into a
+    // straight line so that the prediction has velocities to work with
+    fn new(name: &'static str, borders: Borders, enemy_count: usize) -> Self {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let mut rng = Rng::new(N as u64);
+        let mut current = EnemyPositions::new();
+        let mut previous = EnemyPositions::new();
+        while current.len() < enemy_count {
+            let cell = Coordinate::from_index(rng.below((N * N) as u32) as usize).unwrap();
+            let before = cell - STEPS[rng.below(4) as usize];
+            let taken = current.iter().any(|e| e.position == cell);
+            if taken || borders.is_border(cell) || before.to_index().is_none() || (cell.x.abs() + cell.y.abs()) < 3 {
+                continue;
+            }
+            let id = NonZero::new(current.len() as u64 + 1).unwrap();
+            current.push(EnemyPosition::new(id, cell));
+            previous.push(EnemyPosition::new(id, before));
+        }
+        Scenario { name, robot_position, current, previous, borders }
+    }
+
+    fn empty_arena() -> Self {
+        Self::new("empty", Borders::new(), 3)
+    }
+
+    // walls on every other column with a gap alternately at the top and the bottom
+    fn maze() -> Self {
+        let mut borders = Borders::new();
+        for (i, x) in (-HALF + 1..HALF).step_by(2).enumerate() {
+            let gap = if i % 2 == 0 { -HALF } else { HALF };
+            for y in (-HALF..=HALF).filter(|&y| y != gap) {
+                borders.set_border(Coordinate::new(x, y));
+            }
+        }
+        // keep the robot's cell open whatever the parity of the grid
+        borders.clear_border(Coordinate::new(0, 0));
+        Self::new("maze", borders, 3)
+    }
+
+    fn max_enemies() -> Self {
+        Self::new("max_enemies", Borders::new(), MAX_NUM_ENEMIES)
+    }
+
+    fn coordinates(&self) -> Vec<Coordinate> {
+        self.current.iter().map(|e| e.position).collect()
+    }
+
+    fn prediction(&self) -> EnemyPositionPrediction {
+        EnemyPositionPrediction::new(&self.current, &self.previous, self.borders)
+    }
+}
+
+fn main() {
+    let mut harness = Harness::from_args();
+    if harness.full {
+        println!("{N} x {N} arena, {} ms per tick", TICK_BUDGET.as_millis());
+    }
+
+    for scenario in [Scenario::empty_arena(), Scenario::maze(), Scenario::max_enemies()] {
+        let name = |call: &str| format!("{}/{call}", scenario.name);
+        let coordinates = scenario.coordinates();
+        let mut map: ThreatMap = ThreatMap::new();
+        let prediction = scenario.prediction();
+
+        harness.bench(&name("threat_map_calculate"), || map.calculate(black_box(&coordinates)));
+        harness.bench(&name("threat_map_calculate_with_borders"), || map.calculate_with_borders(black_box(&coordinates), &scenario.borders));
+        harness.bench(&name("threat_map_calculate_with_previous_location"), || map.calculate_with_previous_location(black_box(&scenario.current), &scenario.previous));
+        harness.bench(&name("prediction_new"), || EnemyPositionPrediction::new(black_box(&scenario.current), &scenario.previous, scenario.borders));
+        harness.bench(&name("prediction_move_enemies"), || {
+            let mut moved = black_box(&prediction).clone();
+            moved.move_enemies();
+            moved
+        });
+        harness.bench(&name("greedy_next_move"), || greedy_next_move(black_box(&scenario.robot_position), &prediction, &scenario.borders));
+        harness.bench(&name("lookahead_next_move_2000_nodes"), || {
+            lookahead_next_move(black_box(&scenario.robot_position), &prediction, &scenario.borders, &mut NodeBudget::new(2000))
+        });
+        harness.bench(&name("mcts_next_move_300_iterations"), || {
+            mcts_next_move(black_box(&scenario.robot_position), &prediction, &scenario.borders, &MctsConfig::new())
+        });
+    }
+
+    harness.finish();
+}
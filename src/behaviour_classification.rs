@@ -0,0 +1,112 @@
+use crate::{coordinates::Coordinate, enemy_history::EnemyTrack};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Behaviour {
+    Stationary, Bouncer, RandomWalker, Pursuer
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Classification {
+    pub behaviour: Behaviour,
+    // in percent
+    pub confidence: u8,
+}
+
+pub const MIN_OBSERVATIONS: usize = 3;
+const STATIONARY_THRESHOLD: u32 = 75;
+const RANDOM_THRESHOLD: u32 = 60;
+
+fn percentage(count: u32, total: u32) -> u32 {
+    (count * 100).checked_div(total).unwrap_or(0)
+}
+
+// Each behaviour gets a score from the observed steps of the enemy:
+// - stationary: the fraction of ticks in which the enemy did not move
+// - bouncer: the fraction of consecutive moves that keep or exactly reverse the previous direction
+// - pursuer: the fraction of moves that brought the enemy closer to where the robot was
+// Enemies that match neither a bouncer nor a pursuer well enough are random walkers.
+pub fn classify(track: &EnemyTrack) -> Option<Classification> {
+    if track.len() < MIN_OBSERVATIONS {
+        return None;
+    }
+
+    let steps = track.len() as u32 - 1;
+    let mut still = 0;
+    let mut closer = 0;
+    let mut moves = 0;
+    let mut straight = 0;
+    let mut pairs = 0;
+    let mut previous_step: Option<Coordinate> = None;
+
+    for i in 0..track.len() - 1 {
+        let from = track.position(i);
+        let to = track.position(i + 1);
+        let step = to - from;
+        if step == Coordinate::new(0, 0) {
+            still += 1;
+            continue;
+        }
+
+        moves += 1;
+        let robot_position = track.robot_position(i);
+        if to.distance(robot_position) < from.distance(robot_position) {
+            closer += 1;
+        }
+        if let Some(previous_step) = previous_step {
+            pairs += 1;
+            if step == previous_step || step == -previous_step {
+                straight += 1;
+            }
+        }
+        previous_step = Some(step);
+    }
+
+    let stationary = percentage(still, steps);
+    if stationary >= STATIONARY_THRESHOLD {
+        return Some(Classification { behaviour: Behaviour::Stationary, confidence: stationary as u8 });
+    }
+
+    let bouncer = percentage(straight, pairs);
+    let pursuer = percentage(closer, moves);
+    // an enemy coming straight at a robot that stands still matches both, assume the worst
+    let (behaviour, score) = if pursuer >= bouncer { (Behaviour::Pursuer, pursuer) } else { (Behaviour::Bouncer, bouncer) };
+    if score < RANDOM_THRESHOLD {
+        return Some(Classification { behaviour: Behaviour::RandomWalker, confidence: (100 - score) as u8 });
+    }
+    Some(Classification { behaviour, confidence: score as u8 })
+}
+
+#[cfg(test)]
+mod classification_tests {
+    use core::num::NonZero;
+
+    use super::*;
+
+    fn track(positions: &[(i8, i8)], robot_positions: &[(i8, i8)]) -> EnemyTrack {
+        let mut track = EnemyTrack::new(NonZero::new(1).unwrap());
+        for (&(x, y), &(rx, ry)) in positions.iter().zip(robot_positions) {
+            track.push(Coordinate::new(x, y), Coordinate::new(rx, ry));
+        }
+        track
+    }
+
+    #[test]
+    fn test1() {
+        let t = track(&[(4, 4), (4, 4)], &[(0, 0); 2]);
+        assert_eq!(classify(&t), None);
+
+        let t = track(&[(4, 4), (4, 4), (4, 4), (4, 4), (4, 4)], &[(0, 0); 5]);
+        assert_eq!(classify(&t), Some(Classification { behaviour: Behaviour::Stationary, confidence: 100 }));
+
+        // moving right, bouncing off a border and coming back, while the robot is on the other side
+        let t = track(&[(1, 2), (2, 2), (3, 2), (2, 2), (1, 2), (0, 2)], &[(0, -3); 6]);
+        assert_eq!(classify(&t), Some(Classification { behaviour: Behaviour::Bouncer, confidence: 100 }));
+
+        // the robot keeps moving away, the enemy keeps following
+        let t = track(&[(-3, 0), (-2, 0), (-2, 1), (-1, 1), (-1, 2), (0, 2)], &[(0, 2), (0, 3), (1, 3), (1, 4), (2, 4), (3, 4)]);
+        assert_eq!(classify(&t), Some(Classification { behaviour: Behaviour::Pursuer, confidence: 100 }));
+
+        let t = track(&[(0, 0), (1, 0), (1, 1), (0, 1), (0, 2), (1, 2)], &[(0, -4); 6]);
+        assert_eq!(classify(&t), Some(Classification { behaviour: Behaviour::RandomWalker, confidence: 80 }));
+    }
+}
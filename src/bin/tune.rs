@@ -0,0 +1,74 @@
+// Tunes the evaluation weights against the simulator on the host:
+//
+//     cargo run --release --features std --bin tune -- --scenarios 64 --rounds 10 --out tuned.cfg
+//
+// Options: --scenarios N, --ticks N, --seed N, --rounds N, --step N, --lookahead NODES
+// (tunes the lookahead planner instead of greedy), --config FILE (starts from these weights)
+// and --out FILE (writes the best weights there).
+
+use std::{env, fs, process};
+
+use threat_map::{evaluation::Evaluation, tuning::{measure, parse_config, tune, write_config, write_rust_const, SurvivalStats, TunedPlanner, TuningSetup}};
+
+fn print_stats(label: &str, stats: &SurvivalStats, max_ticks: u32) {
+    println!(
+        "{label}: mean {:.1} ticks, worst {}, {}/{} runs survived all {max_ticks} ticks",
+        stats.total_ticks as f64 / stats.runs.max(1) as f64,
+        stats.min_ticks,
+        stats.survived,
+        stats.runs
+    );
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("tune: {message}");
+    process::exit(2)
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: Option<String>) -> T {
+    value.and_then(|v| v.parse().ok()).unwrap_or_else(|| fail(&format!("{option} needs a number")))
+}
+
+fn main() {
+    let mut setup = TuningSetup::new();
+    let mut rounds = 8;
+    let mut step = 4;
+    let mut start = Evaluation::new();
+    let mut out = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(option) = args.next() {
+        match option.as_str() {
+            "--scenarios" => setup.scenarios = number(&option, args.next()),
+            "--ticks" => setup.max_ticks = number(&option, args.next()),
+            "--seed" => setup.first_seed = number(&option, args.next()),
+            "--rounds" => rounds = number(&option, args.next()),
+            "--step" => step = number(&option, args.next()),
+            "--lookahead" => setup.planner = TunedPlanner::Lookahead(number(&option, args.next())),
+            "--config" => {
+                let path = args.next().unwrap_or_else(|| fail("--config needs a file"));
+                let text = fs::read_to_string(&path).unwrap_or_else(|e| fail(&format!("cannot read {path}: {e}")));
+                start = parse_config(&text).unwrap_or_else(|| fail(&format!("cannot parse {path}")));
+            }
+            "--out" => out = Some(args.next().unwrap_or_else(|| fail("--out needs a file"))),
+            _ => fail(&format!("unknown option {option}")),
+        }
+    }
+
+    print_stats("start", &measure(&setup, &start), setup.max_ticks);
+    let (best, stats) = tune(&setup, start, rounds, step, |round, evaluation, stats| {
+        print_stats(&format!("round {round} {:?}", evaluation.weights()), stats, setup.max_ticks);
+    });
+    print_stats("best", &stats, setup.max_ticks);
+
+    let mut rust = String::new();
+    write_rust_const(&best, "TUNED_EVALUATION", &mut rust).unwrap();
+    println!("\n{rust}");
+
+    if let Some(path) = out {
+        let mut config = String::new();
+        write_config(&best, &mut config).unwrap();
+        fs::write(&path, config).unwrap_or_else(|e| fail(&format!("cannot write {path}: {e}")));
+        println!("wrote {path}");
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, occupancy::{Occupancy, OccupancyMap}, robot_position::RobotPosition, N};
+
+// Evidence is accumulated as log-odds per cell: hits push a cell towards blocked and misses
+// towards free, so a single noisy reading never decides the state of a cell on its own.
+pub const LOG_ODDS_HIT: i8 = 2;
+pub const LOG_ODDS_MISS: i8 = -1;
+pub const LOG_ODDS_BUMP: i8 = 2;
+pub const LOG_ODDS_LIMIT: i8 = 12;
+pub const BLOCKED_THRESHOLD: i8 = 3;
+pub const FREE_THRESHOLD: i8 = -2;
+
+#[derive(Debug, Clone)]
+pub struct BorderEvidence {
+    log_odds: [i8; N * N]
+}
+
+impl BorderEvidence {
+    pub fn new() -> Self {
+        BorderEvidence { log_odds: [0; N * N] }
+    }
+
+    pub fn log_odds_at(&self, coord: Coordinate) -> i8 {
+        match coord.to_index() {
+            Some(i) => self.log_odds[i],
+            None => 0
+        }
+    }
+
+    fn update(&mut self, coord: Coordinate, delta: i8) {
+        if let Some(i) = coord.to_index() {
+            self.log_odds[i] = self.log_odds[i].saturating_add(delta).clamp(-LOG_ODDS_LIMIT, LOG_ODDS_LIMIT);
+        }
+    }
+
+    pub fn record_hit(&mut self, coord: Coordinate) {
+        self.update(coord, LOG_ODDS_HIT);
+    }
+
+    pub fn record_miss(&mut self, coord: Coordinate) {
+        self.update(coord, LOG_ODDS_MISS);
+    }
+
+    // Call with the robot position before and after trying `take_step(Direction::Front)`.
+    // Returns true if the robot bumped into something, i.e. its position did not change.
+    pub fn record_bump(&mut self, before: &RobotPosition, after: &RobotPosition) -> bool {
+        if before.position == after.position {
+            self.update(before.in_direction(Direction::Front), LOG_ODDS_BUMP);
+            true
+        } else {
+            self.record_miss(after.position);
+            false
+        }
+    }
+
+    // A range sensor looking in `sensor_direction` relative to the robot. `reading` is the distance
+    // in cells to the first obstacle, or None if nothing was seen within `max_range` cells.
+    pub fn record_range(&mut self, robot_position: &RobotPosition, sensor_direction: Direction, reading: Option<u8>, max_range: u8) {
+        let orientation = robot_position.orientation;
+        let mut coord = robot_position.position;
+        let free_cells = match reading {
+            Some(distance) => distance.saturating_sub(1).min(max_range),
+            None => max_range,
+        };
+
+        for _ in 0..free_cells {
+            coord = coord.in_direction(sensor_direction, orientation);
+            self.record_miss(coord);
+        }
+        if let Some(distance) = reading {
+            if distance >= 1 && distance <= max_range {
+                self.record_hit(coord.in_direction(sensor_direction, orientation));
+            }
+        }
+    }
+
+    pub fn occupancy_at(&self, coord: Coordinate) -> Occupancy {
+        let log_odds = self.log_odds_at(coord);
+        if coord.to_index().is_none() || log_odds >= BLOCKED_THRESHOLD {
+            Occupancy::Blocked
+        } else if log_odds <= FREE_THRESHOLD {
+            Occupancy::Free
+        } else {
+            Occupancy::Unknown
+        }
+    }
+
+    pub fn to_occupancy_map(&self) -> OccupancyMap {
+        let mut occupancy = OccupancyMap::new();
+        for index in 0..(N * N) {
+            let coord = Coordinate::from_index(index).unwrap();
+            occupancy.set(coord, self.occupancy_at(coord));
+        }
+        occupancy
+    }
+
+    // sets the cells that are confidently blocked and clears the ones that are confidently free,
+    // cells without enough evidence either way are left as they are
+    pub fn update_borders(&self, borders: &mut Borders) {
+        for index in 0..(N * N) {
+            let coord = Coordinate::from_index(index).unwrap();
+            match self.occupancy_at(coord) {
+                Occupancy::Blocked => borders.set_border(coord),
+                Occupancy::Free => borders.clear_border(coord),
+                Occupancy::Unknown => {}
+            }
+        }
+    }
+}
+
+impl Default for BorderEvidence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod border_learning_tests {
+    use crate::orientation::Orientation;
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::East };
+        let mut evidence = BorderEvidence::new();
+        let mut borders = Borders::new();
+
+        // a single reading is not enough to wall off a cell
+        evidence.record_range(&robot_position, Direction::Front, Some(3), 4);
+        evidence.update_borders(&mut borders);
+        assert!(!borders.is_border(Coordinate::new(3, 0)));
+        assert_eq!(evidence.occupancy_at(Coordinate::new(3, 0)), Occupancy::Unknown);
+        assert_eq!(evidence.log_odds_at(Coordinate::new(1, 0)), LOG_ODDS_MISS);
+
+        evidence.record_range(&robot_position, Direction::Front, Some(3), 4);
+        evidence.update_borders(&mut borders);
+        assert!(borders.is_border(Coordinate::new(3, 0)));
+        assert_eq!(evidence.occupancy_at(Coordinate::new(1, 0)), Occupancy::Free);
+        assert_eq!(evidence.occupancy_at(Coordinate::new(4, 0)), Occupancy::Unknown);
+
+        // later readings passing through the cell clear it again
+        for _ in 0..6 {
+            evidence.record_range(&robot_position, Direction::Front, None, 4);
+        }
+        evidence.update_borders(&mut borders);
+        assert!(!borders.is_border(Coordinate::new(3, 0)));
+
+        // the left sensor of a robot facing east looks north
+        evidence.record_range(&robot_position, Direction::Left, Some(1), 4);
+        assert_eq!(evidence.log_odds_at(Coordinate::new(0, -1)), LOG_ODDS_HIT);
+    }
+
+    #[test]
+    fn test2() {
+        let mut evidence = BorderEvidence::new();
+        let before = RobotPosition { position: Coordinate::new(1, 1), orientation: Orientation::South };
+
+        let mut after = before;
+        after.take_step(Direction::Front);
+        assert!(!evidence.record_bump(&before, &after));
+        assert_eq!(evidence.log_odds_at(Coordinate::new(1, 2)), LOG_ODDS_MISS);
+
+        // one bump could be a wheel slipping, the second one walls the cell off
+        assert!(evidence.record_bump(&after, &after));
+        assert_eq!(evidence.occupancy_at(Coordinate::new(1, 3)), Occupancy::Unknown);
+        assert!(evidence.record_bump(&after, &after));
+        assert_eq!(evidence.occupancy_at(Coordinate::new(1, 3)), Occupancy::Blocked);
+        assert_eq!(evidence.to_occupancy_map().at(Coordinate::new(1, 3)), Occupancy::Blocked);
+    }
+}
@@ -1,34 +1,332 @@
-use crate::{coordinates::Coordinate, N};
+use core::ops::{BitAnd, BitOr, BitOrAssign, Sub};
 
-#[derive(Debug, Clone)]
+use crate::{coordinates::Coordinate, direction::Direction, orientation::Orientation, N};
+
+const WORDS: usize = (N * N).div_ceil(32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Borders {
-    borders: [[bool; N]; N]
+    bits: [u32; WORDS]
 }
 
 impl Borders {
     pub fn new() -> Self {
-        Self { borders: [[false; N]; N]}
-    }
-
-    fn border_indices(&self, coord: Coordinate) -> (usize, usize) {
-        let x = coord.x + (N as i8)/2;
-        let y = coord.y + (N as i8)/2;
-        (x as usize, y as usize)
+        Self { bits: [0; WORDS] }
     }
 
     pub fn is_border(&self, coord: Coordinate) -> bool {
-        if coord.x < -(N as i8)/2 ||
-           coord.x >  (N as i8)/2 ||
-           coord.y < -(N as i8)/2 ||
-           coord.y >  (N as i8)/2 {
-            return false;
+        match coord.to_index() {
+            Some(i) => self.bits[i / 32] & (1 << (i % 32)) != 0,
+            None => false
         }
-        let (x, y) = self.border_indices(coord);
-        self.borders[x][y]
     }
 
     pub fn set_border(&mut self, coord: Coordinate) {
-        let (x, y) = self.border_indices(coord);
-        self.borders[x][y] = true;
+        if let Some(i) = coord.to_index() {
+            self.bits[i / 32] |= 1 << (i % 32);
+        }
+    }
+
+    pub fn clear_border(&mut self, coord: Coordinate) {
+        if let Some(i) = coord.to_index() {
+            self.bits[i / 32] &= !(1 << (i % 32));
+        }
+    }
+
+    pub fn toggle(&mut self, coord: Coordinate) {
+        if let Some(i) = coord.to_index() {
+            self.bits[i / 32] ^= 1 << (i % 32);
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&w| w == 0)
+    }
+
+    pub fn iter(&self) -> BorderIter {
+        BorderIter { borders: *self, index: 0 }
+    }
+
+    // every border moved by `offset`, borders moved out of the arena are dropped
+    pub fn shifted(&self, offset: Coordinate) -> Borders {
+        let mut shifted = Borders::new();
+        for coord in self.iter() {
+            let x = coord.x as i32 + offset.x as i32;
+            let y = coord.y as i32 + offset.y as i32;
+            if x.abs() <= (N as i32) / 2 && y.abs() <= (N as i32) / 2 {
+                shifted.set_border(Coordinate::new(x as i8, y as i8));
+            }
+        }
+        shifted
+    }
+
+    // the same borders as seen by a robot at the centre facing `orientation`
+    pub fn rotated(&self, orientation: Orientation) -> Borders {
+        let mut rotated = Borders::new();
+        for coord in self.iter() {
+            rotated.set_border(coord.orientate_north(orientation));
+        }
+        rotated
+    }
+
+    // grows every border by `k` cells in all eight directions, e.g. to keep a safety margin around walls
+    pub fn dilated(&self, k: i8) -> Borders {
+        let mut dilated = *self;
+        for dy in -k..=k {
+            for dx in -k..=k {
+                dilated |= self.shifted(Coordinate::new(dx, dy));
+            }
+        }
+        dilated
+    }
+
+    // keeps only the borders that have borders all around them within `k` cells,
+    // the outside of the arena counts as a border
+    pub fn eroded(&self, k: i8) -> Borders {
+        let mut eroded = Borders::new();
+        for coord in self.iter() {
+            let surrounded = (-k..=k).all(|dy| (-k..=k).all(|dx| {
+                let x = coord.x as i32 + dx as i32;
+                let y = coord.y as i32 + dy as i32;
+                x.abs() > (N as i32) / 2 || y.abs() > (N as i32) / 2 || self.is_border(Coordinate::new(x as i8, y as i8))
+            }));
+            if surrounded {
+                eroded.set_border(coord);
+            }
+        }
+        eroded
+    }
+
+    // labels the four-connected groups of border cells
+    pub fn connected_components(&self) -> ComponentLabels {
+        let mut labels = ComponentLabels { labels: [0; N * N], count: 0 };
+        let mut stack = [0usize; N * N];
+
+        for start in self.iter() {
+            let start_index = start.to_index().unwrap();
+            if labels.labels[start_index] != 0 {
+                continue;
+            }
+            labels.count += 1;
+            labels.labels[start_index] = labels.count as u8;
+            stack[0] = start_index;
+            let mut length = 1;
+
+            while length > 0 {
+                length -= 1;
+                let coord = Coordinate::from_index(stack[length]).unwrap();
+                for direction in [Direction::Front, Direction::Right, Direction::Back, Direction::Left] {
+                    let next = coord.in_direction(direction, Orientation::North);
+                    if !self.is_border(next) {
+                        continue;
+                    }
+                    let next_index = next.to_index().unwrap();
+                    if labels.labels[next_index] == 0 {
+                        labels.labels[next_index] = labels.count as u8;
+                        stack[length] = next_index;
+                        length += 1;
+                    }
+                }
+            }
+        }
+        labels
+    }
+}
+
+impl Default for Borders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BitOr for Borders {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        let mut result = self;
+        result |= rhs;
+        result
+    }
+}
+
+impl BitOrAssign for Borders {
+    fn bitor_assign(&mut self, rhs: Self) {
+        for (word, other) in self.bits.iter_mut().zip(rhs.bits) {
+            *word |= other;
+        }
+    }
+}
+
+impl BitAnd for Borders {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        let mut result = self;
+        for (word, other) in result.bits.iter_mut().zip(rhs.bits) {
+            *word &= other;
+        }
+        result
+    }
+}
+
+impl Sub for Borders {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let mut result = self;
+        for (word, other) in result.bits.iter_mut().zip(rhs.bits) {
+            *word &= !other;
+        }
+        result
+    }
+}
+
+pub struct BorderIter {
+    borders: Borders,
+    index: usize,
+}
+
+impl Iterator for BorderIter {
+    type Item = Coordinate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < N * N {
+            let index = self.index;
+            self.index += 1;
+            if self.borders.bits[index / 32] & (1 << (index % 32)) != 0 {
+                return Coordinate::from_index(index);
+            }
+        }
+        None
+    }
+}
+
+impl IntoIterator for &Borders {
+    type Item = Coordinate;
+
+    type IntoIter = BorderIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ComponentLabels {
+    labels: [u8; N * N],
+    count: usize,
+}
+
+impl ComponentLabels {
+    pub fn count(&self) -> usize {
+        self.count
     }
-}
\ No newline at end of file
+
+    // components are numbered from 1
+    pub fn label_at(&self, coord: Coordinate) -> Option<u8> {
+        match coord.to_index().map(|i| self.labels[i]) {
+            Some(0) | None => None,
+            label => label
+        }
+    }
+}
+
+#[cfg(test)]
+mod borders_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let half = (N / 2) as i8;
+        let mut borders = Borders::new();
+        assert!(borders.is_empty());
+        borders.set_border(Coordinate::new(4, 4));
+        borders.set_border(Coordinate::new(-4, -4));
+        borders.set_border(Coordinate::new(half + 3, 0));
+        borders.toggle(Coordinate::new(0, 0));
+        assert!(borders.is_border(Coordinate::new(4, 4)));
+        assert!(borders.is_border(Coordinate::new(0, 0)));
+        assert!(!borders.is_border(Coordinate::new(half + 3, 0)));
+        assert_eq!(borders.count(), 3);
+
+        borders.toggle(Coordinate::new(0, 0));
+        borders.clear_border(Coordinate::new(4, 4));
+        assert!(borders.iter().eq([Coordinate::new(-4, -4)]));
+    }
+
+    #[test]
+    fn test2() {
+        let mut a = Borders::new();
+        let mut b = Borders::new();
+        a.set_border(Coordinate::new(0, 0));
+        a.set_border(Coordinate::new(1, 0));
+        b.set_border(Coordinate::new(1, 0));
+        b.set_border(Coordinate::new(2, 0));
+
+        assert_eq!((a | b).count(), 3);
+        assert_eq!((a & b).iter().next(), Some(Coordinate::new(1, 0)));
+        assert_eq!((a & b).count(), 1);
+        assert_eq!((a - b).iter().next(), Some(Coordinate::new(0, 0)));
+        assert_eq!((a - b).count(), 1);
+    }
+
+    #[test]
+    fn test3() {
+        let half = (N / 2) as i8;
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(0, 0));
+        borders.set_border(Coordinate::new(half, 0));
+
+        let dilated = borders.dilated(1);
+        assert_eq!(dilated.count(), 9 + 6);
+        assert!(dilated.is_border(Coordinate::new(-1, -1)));
+        assert!(dilated.is_border(Coordinate::new(half - 1, 1)));
+
+        // the block in the middle shrinks back to its centre, the one at the edge keeps its edge
+        let eroded = dilated.eroded(1);
+        assert!(eroded.is_border(Coordinate::new(0, 0)));
+        assert!(eroded.is_border(Coordinate::new(half, 0)));
+        assert_eq!(eroded.count(), 2);
+        assert_eq!(borders.dilated(0), borders);
+    }
+
+    #[test]
+    fn test4() {
+        let mut borders = Borders::new();
+        for x in -4..=-2 {
+            borders.set_border(Coordinate::new(x, 0));
+        }
+        borders.set_border(Coordinate::new(2, 2));
+        borders.set_border(Coordinate::new(2, 3));
+        borders.set_border(Coordinate::new(3, 4));
+
+        let labels = borders.connected_components();
+        assert_eq!(labels.count(), 3);
+        assert_eq!(labels.label_at(Coordinate::new(-4, 0)), labels.label_at(Coordinate::new(-2, 0)));
+        assert_eq!(labels.label_at(Coordinate::new(2, 2)), labels.label_at(Coordinate::new(2, 3)));
+        assert_ne!(labels.label_at(Coordinate::new(2, 3)), labels.label_at(Coordinate::new(3, 4)));
+        assert_eq!(labels.label_at(Coordinate::new(0, 0)), None);
+    }
+
+    #[test]
+    fn test5() {
+        let half = (N / 2) as i8;
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(0, 3));
+        borders.set_border(Coordinate::new(half, half));
+
+        let shifted = borders.shifted(Coordinate::new(1, -1));
+        assert!(shifted.is_border(Coordinate::new(1, 2)));
+        assert_eq!(shifted.count(), 1);
+
+        let rotated = borders.rotated(Orientation::East);
+        assert!(rotated.is_border(Coordinate::new(0, 3).orientate_north(Orientation::East)));
+        assert!(rotated.is_border(Coordinate::new(half, -half)));
+        assert_eq!(borders.rotated(Orientation::North), borders);
+        assert_eq!(rotated.rotated(Orientation::West), borders);
+    }
+}
@@ -0,0 +1,103 @@
+// How long a planner may search. Planners call `spend` once for every node they expand and stop
+// with their best move so far as soon as it returns false.
+pub trait Budget {
+    fn spend(&mut self) -> bool;
+}
+
+// A fixed number of nodes, for deterministic planning and for tests.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeBudget {
+    remaining: u32,
+}
+
+impl NodeBudget {
+    pub fn new(nodes: u32) -> Self {
+        NodeBudget { remaining: nodes }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+}
+
+impl Budget for NodeBudget {
+    fn spend(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        true
+    }
+}
+
+// Asks the caller whether time is up, there is no clock in `no_std`. The callback is only asked
+// every `check_interval` nodes, so it may be as slow as reading a hardware timer.
+#[derive(Debug, Clone, Copy)]
+pub struct CallbackBudget {
+    time_is_up: fn() -> bool,
+    check_interval: u32,
+    until_check: u32,
+    expired: bool,
+}
+
+impl CallbackBudget {
+    pub fn new(time_is_up: fn() -> bool) -> Self {
+        CallbackBudget { time_is_up, check_interval: 1, until_check: 0, expired: false }
+    }
+
+    pub fn use_check_interval(&mut self, check_interval: u32) {
+        self.check_interval = check_interval.max(1);
+        self.until_check = 0;
+    }
+}
+
+impl Budget for CallbackBudget {
+    fn spend(&mut self) -> bool {
+        if self.expired {
+            return false;
+        }
+        if self.until_check == 0 {
+            self.expired = (self.time_is_up)();
+            self.until_check = self.check_interval;
+        }
+        self.until_check -= 1;
+        !self.expired
+    }
+}
+
+#[cfg(test)]
+mod budget_tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut budget = NodeBudget::new(3);
+        assert!(budget.spend());
+        assert!(budget.spend());
+        assert_eq!(budget.remaining(), 1);
+        assert!(budget.spend());
+        assert!(!budget.spend());
+        assert!(!NodeBudget::new(0).spend());
+    }
+
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    // time is up on the third look at the clock
+    fn third_call() -> bool {
+        CALLS.fetch_add(1, Ordering::Relaxed) >= 2
+    }
+
+    #[test]
+    fn test2() {
+        let mut budget = CallbackBudget::new(third_call);
+        budget.use_check_interval(4);
+        let spent = (0..100).take_while(|_| budget.spend()).count();
+        assert_eq!(spent, 8);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 3);
+        // once expired the clock is not asked again
+        assert!(!budget.spend());
+        assert_eq!(CALLS.load(Ordering::Relaxed), 3);
+    }
+}
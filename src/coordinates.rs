@@ -1,6 +1,9 @@
 use core::ops::{Add, AddAssign, Neg, Sub};
 
-use crate::{direction::Direction, orientation::Orientation, N};
+use crate::{direction::Direction, metric::Connectivity, orientation::Orientation, N};
+
+// the four orthogonal neighbours first, then the diagonal ones
+const NEIGHBOUR_OFFSETS: [(i8, i8); 8] = [(0, -1), (1, 0), (0, 1), (-1, 0), (1, -1), (1, 1), (-1, 1), (-1, -1)];
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
 pub struct Coordinate {
@@ -47,6 +50,16 @@ impl Coordinate {
         self.x.abs_diff(other.x) as i32 + self.y.abs_diff(other.y) as i32
     }
 
+    // the neighbouring cells, including the ones outside of the map
+    pub fn neighbours(&self, connectivity: Connectivity) -> impl Iterator<Item = Coordinate> {
+        let count = match connectivity {
+            Connectivity::Four => 4,
+            Connectivity::Eight => 8,
+        };
+        let coord = *self;
+        NEIGHBOUR_OFFSETS[..count].iter().map(move |&(x, y)| Coordinate::new(coord.x + x, coord.y + y))
+    }
+
     fn rotate_right(&self) -> Coordinate {
         Coordinate { x: self.y, y: -self.x }
     }
@@ -95,44 +108,26 @@ impl Coordinate {
     }
 
     pub fn orientate_north(&self, orientation: Orientation) -> Coordinate {
-        self.rotate(Orientation::North.direction_relative_to(orientation))
+        self.rotate(orientation.direction_relative_to(Orientation::North))
     }
 
     pub fn is_corner(&self) -> bool {
-        (self.x == -(N as i8)/2 && self.y == -(N as i8)/2) ||
-        (self.x == -(N as i8)/2 && self.y ==  (N as i8)/2) || 
-        (self.x ==  (N as i8)/2 && self.y == -(N as i8)/2) || 
-        (self.x ==  (N as i8)/2 && self.y ==  (N as i8)/2)
+        self.x.abs() == (N as i8)/2 && self.y.abs() == (N as i8)/2
     }
 
+    // the unit step along the longer axis, ties go to the y axis
     pub fn normalized(&self) -> Coordinate {
         if self.x.abs() > self.y.abs() {
-            if self.x > 0 {
-                Coordinate::new(1, 0)
-            } else if self.x < 0 {
-                Coordinate::new(-1, 0)
-            } else {
-                // because 
-                // y.abs() >= 0
-                // x.abs() > y.abs()
-                // =>
-                // x.abs() >= y.abs() + 1
-                // =>
-                // x.abs() >= 0 + 1
-                // =>
-                // x > 1 || x < 1
-                unreachable!()
-            }
+            Coordinate::new(self.x.signum(), 0)
         } else {
-            if self.y > 0 {
-                Coordinate::new(0, 1)
-            } else if self.y < 0 {
-                Coordinate::new(0, -1)
-            } else {
-                Coordinate::new(0, 0)
-            }
+            Coordinate::new(0, self.y.signum())
         }
     }
+
+    // the unit step in the direction of the vector, diagonals included
+    pub fn signum(&self) -> Coordinate {
+        Coordinate::new(self.x.signum(), self.y.signum())
+    }
 }
 
 impl Add for Coordinate {
@@ -174,9 +169,10 @@ mod coordinate_tests {
 
     #[test]
     fn test1() {
+        let half = (N / 2) as i8;
         let mut expected_index = 0;
-        for y in -4..=4 {
-            for x in -4..=4 {
+        for y in -half..=half {
+            for x in -half..=half {
                 let c = Coordinate::new(x, y);
                 assert_eq!(c.to_index(), Some(expected_index));
                 assert_eq!(Coordinate::from_index(expected_index), Some(c));
@@ -197,7 +193,7 @@ mod coordinate_tests {
         assert_eq!(c1, Coordinate::new(-3, -3));
 
         let c1 = Coordinate::new(-4, 2).orientate_north(Orientation::West);
-        assert_eq!(c1, Coordinate::new(-3, -3));
+        assert_eq!(c1, Coordinate::new(-2, -4));
     }
 
     #[test]
@@ -218,4 +214,14 @@ mod coordinate_tests {
         let c = Coordinate::new(1, -2) + Coordinate::new(3, 1);
         assert_eq!(c, Coordinate::new(4, -1));
     }
+
+    #[test]
+    fn test5() {
+        let half = (N / 2) as i8;
+        let c = Coordinate::new(half, 0);
+        assert_eq!(c.neighbours(Connectivity::Four).count(), 4);
+        assert!(c.neighbours(Connectivity::Four).all(|n| c.distance(n) == 1));
+        assert_eq!(c.neighbours(Connectivity::Eight).filter(|n| n.to_index().is_some()).count(), 5);
+        assert!(c.neighbours(Connectivity::Eight).any(|n| n == Coordinate::new(half - 1, -1)));
+    }
 }
@@ -0,0 +1,197 @@
+use core::num::NonZero;
+
+use crate::{coordinates::Coordinate, enemy_position::EnemyPositions, metric::Metric, HISTORY_LENGTH, MAX_NUM_ENEMIES};
+
+// The last HISTORY_LENGTH observed positions of one enemy, together with where the robot was
+// at the time of each observation.
+#[derive(Debug, Clone, Copy)]
+pub struct EnemyTrack {
+    pub id: NonZero<u64>,
+    positions: [Coordinate; HISTORY_LENGTH],
+    robot_positions: [Coordinate; HISTORY_LENGTH],
+    length: usize,
+    next: usize,
+    last_seen: u32,
+}
+
+impl EnemyTrack {
+    pub fn new(id: NonZero<u64>) -> Self {
+        EnemyTrack { id, positions: [Coordinate::new(0, 0); HISTORY_LENGTH], robot_positions: [Coordinate::new(0, 0); HISTORY_LENGTH], length: 0, next: 0, last_seen: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn push(&mut self, position: Coordinate, robot_position: Coordinate) {
+        self.positions[self.next] = position;
+        self.robot_positions[self.next] = robot_position;
+        self.next = (self.next + 1) % HISTORY_LENGTH;
+        self.length = (self.length + 1).min(HISTORY_LENGTH);
+    }
+
+    fn slot(&self, i: usize) -> usize {
+        (self.next + HISTORY_LENGTH - self.length + i) % HISTORY_LENGTH
+    }
+
+    // index 0 is the oldest observation
+    pub fn position(&self, i: usize) -> Coordinate {
+        self.positions[self.slot(i)]
+    }
+
+    pub fn robot_position(&self, i: usize) -> Coordinate {
+        self.robot_positions[self.slot(i)]
+    }
+
+    pub fn latest_position(&self) -> Option<Coordinate> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.position(self.length - 1))
+        }
+    }
+
+    // Distance per tick in `metric`, averaged over the track and rounded. Observations are assumed
+    // to be one tick apart.
+    pub fn estimated_speed(&self, metric: Metric) -> Option<i32> {
+        if self.length < 2 {
+            return None;
+        }
+        let total: i32 = (1..self.length).map(|i| metric.distance(self.position(i - 1), self.position(i))).sum();
+        let steps = self.length as i32 - 1;
+        Some((total + steps / 2) / steps)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnemyHistory {
+    tracks: [EnemyTrack; MAX_NUM_ENEMIES],
+    length: usize,
+    tick: u32,
+}
+
+impl EnemyHistory {
+    pub fn new() -> Self {
+        EnemyHistory { tracks: [EnemyTrack::new(NonZero::new(1).unwrap()); MAX_NUM_ENEMIES], length: 0, tick: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    // Records one tick of observations. When all tracks are in use, the track of the enemy
+    // that has not been seen for the longest time is reused.
+    pub fn record(&mut self, enemy_positions: &EnemyPositions, robot_position: Coordinate) {
+        self.tick += 1;
+        for enemy in enemy_positions {
+            let index = match self.tracks[..self.length].iter().position(|t| t.id == enemy.id) {
+                Some(index) => index,
+                None if self.length < MAX_NUM_ENEMIES => {
+                    self.length += 1;
+                    self.tracks[self.length - 1] = EnemyTrack::new(enemy.id);
+                    self.length - 1
+                }
+                None => {
+                    let (index, _) = self.tracks.iter().enumerate().min_by_key(|(_, t)| t.last_seen).unwrap();
+                    self.tracks[index] = EnemyTrack::new(enemy.id);
+                    index
+                }
+            };
+            self.tracks[index].push(enemy.position, robot_position);
+            self.tracks[index].last_seen = self.tick;
+        }
+    }
+
+    pub fn track_of(&self, id: NonZero<u64>) -> Option<&EnemyTrack> {
+        self.tracks[..self.length].iter().find(|t| t.id == id)
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, EnemyTrack> {
+        self.tracks[..self.length].iter()
+    }
+}
+
+impl Default for EnemyHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod enemy_history_tests {
+    use crate::enemy_position::EnemyPosition;
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let id = NonZero::new(7).unwrap();
+        let mut history = EnemyHistory::new();
+        for x in 0..(HISTORY_LENGTH as i8 + 3) {
+            let mut enemies = EnemyPositions::new();
+            enemies.push(EnemyPosition::new(id, Coordinate::new(x, 0)));
+            history.record(&enemies, Coordinate::new(0, x));
+        }
+
+        assert_eq!(history.len(), 1);
+        let track = history.track_of(id).unwrap();
+        assert_eq!(track.len(), HISTORY_LENGTH);
+        assert_eq!(track.position(0), Coordinate::new(3, 0));
+        assert_eq!(track.robot_position(0), Coordinate::new(0, 3));
+        assert_eq!(track.latest_position(), Some(Coordinate::new(HISTORY_LENGTH as i8 + 2, 0)));
+        assert!(history.track_of(NonZero::new(1).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test2() {
+        let mut history = EnemyHistory::new();
+        for id in 1..=MAX_NUM_ENEMIES as u64 {
+            let mut enemies = EnemyPositions::new();
+            enemies.push(EnemyPosition::new(NonZero::new(id).unwrap(), Coordinate::new(0, 0)));
+            history.record(&enemies, Coordinate::new(0, 0));
+        }
+
+        // the enemy that was seen first gets evicted
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(100).unwrap(), Coordinate::new(0, 0)));
+        history.record(&enemies, Coordinate::new(0, 0));
+        assert_eq!(history.len(), MAX_NUM_ENEMIES);
+        assert!(history.track_of(NonZero::new(1).unwrap()).is_none());
+        assert!(history.track_of(NonZero::new(2).unwrap()).is_some());
+        assert!(history.track_of(NonZero::new(100).unwrap()).is_some());
+    }
+
+    #[test]
+    fn test3() {
+        let id = NonZero::new(3).unwrap();
+        let mut history = EnemyHistory::new();
+        for (tick, x) in [-4, -2, 0, 2, 3].into_iter().enumerate() {
+            let mut enemies = EnemyPositions::new();
+            enemies.push(EnemyPosition::new(id, Coordinate::new(x, 0)));
+            history.record(&enemies, Coordinate::new(0, 0));
+            if tick == 0 {
+                assert_eq!(history.track_of(id).unwrap().estimated_speed(Metric::Manhattan), None);
+            }
+        }
+        // 7 cells in 4 ticks
+        assert_eq!(history.track_of(id).unwrap().estimated_speed(Metric::Manhattan), Some(2));
+
+        // one diagonal step per tick is two cells in Manhattan distance
+        let id = NonZero::new(4).unwrap();
+        for i in 0..3 {
+            let mut enemies = EnemyPositions::new();
+            enemies.push(EnemyPosition::new(id, Coordinate::new(i, i)));
+            history.record(&enemies, Coordinate::new(0, 0));
+        }
+        assert_eq!(history.track_of(id).unwrap().estimated_speed(Metric::Manhattan), Some(2));
+        assert_eq!(history.track_of(id).unwrap().estimated_speed(Metric::Chebyshev), Some(1));
+    }
+}
@@ -3,15 +3,66 @@ use core::num::NonZero;
 use crate::coordinates::Coordinate;
 use crate::MAX_NUM_ENEMIES;
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EnemyKind {
+    Generic, Drone, Rammer
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EnemyAttributes {
+    pub kind: EnemyKind,
+    // cells per tick
+    pub speed: i32,
+    // cells around the enemy that are as dangerous as the enemy itself
+    pub danger_radius: i32,
+    // in percent, an enemy with weight 200 is as threatening as one half as far away with weight 100,
+    // an enemy with weight 0 is harmless
+    pub weight: i32,
+}
+
+impl EnemyAttributes {
+    pub fn new() -> Self {
+        EnemyAttributes { kind: EnemyKind::Generic, speed: 1, danger_radius: 0, weight: 100 }
+    }
+
+    pub fn of_kind(kind: EnemyKind) -> Self {
+        match kind {
+            EnemyKind::Generic => Self::new(),
+            EnemyKind::Drone => EnemyAttributes { kind, speed: 1, danger_radius: 0, weight: 50 },
+            EnemyKind::Rammer => EnemyAttributes { kind, speed: 2, danger_radius: 1, weight: 200 },
+        }
+    }
+
+    // the distance this enemy counts as, given the real distance to it
+    pub fn effective_distance(&self, distance: i32) -> i32 {
+        if self.weight <= 0 || distance == i32::MAX {
+            return i32::MAX;
+        }
+        let distance = (distance as i64 - self.danger_radius as i64).max(0);
+        (distance * 100 / self.weight as i64).min(i32::MAX as i64) as i32
+    }
+}
+
+impl Default for EnemyAttributes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct EnemyPosition {
     pub id: NonZero<u64>,
     pub position: Coordinate,
+    pub attributes: EnemyAttributes,
 }
 
 impl EnemyPosition {
     pub fn new(id: NonZero<u64>, position: Coordinate) -> Self {
-        Self { id, position }
+        Self { id, position, attributes: EnemyAttributes::new() }
+    }
+
+    pub fn with_attributes(id: NonZero<u64>, position: Coordinate, attributes: EnemyAttributes) -> Self {
+        Self { id, position, attributes }
     }
 }
 
@@ -31,6 +82,10 @@ impl EnemyPositions {
         self.length
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
     pub fn clear(&mut self) {
         self.length = 0;
     }
@@ -43,25 +98,43 @@ impl EnemyPositions {
         self.length += 1;
     }
 
+    pub fn remove(&mut self, id: NonZero<u64>) -> Option<EnemyPosition> {
+        let index = self.iter().position(|e| e.id == id)?;
+        let removed = self.enemy_positions[index];
+        self.enemy_positions[index] = self.enemy_positions[self.len() - 1];
+        self.length -= 1;
+        Some(removed)
+    }
+
     pub fn use_origin(&mut self, origin: Coordinate) {
         self.origin = origin
     }
 
+    pub fn origin(&self) -> Coordinate {
+        self.origin
+    }
+
     pub fn get_position_of(&self, id: NonZero<u64>) -> Option<Coordinate> {
-        let enemy_position = self.enemy_positions.iter().find(|&e| e.id == id).copied()?;
+        let enemy_position = self.iter().find(|&e| e.id == id).copied()?;
         Some(enemy_position.position - self.origin)
     }
 
-    pub fn iter(&self) -> core::slice::Iter<EnemyPosition> {
+    pub fn iter(&self) -> core::slice::Iter<'_, EnemyPosition> {
         self.enemy_positions[..self.len()].iter()
     }
 
-    pub fn iter_mut(&mut self) -> core::slice::IterMut<EnemyPosition> {
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, EnemyPosition> {
         let len: usize = self.len();
         self.enemy_positions[..len].iter_mut()
     }
 }
 
+impl Default for EnemyPositions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> IntoIterator for &'a EnemyPositions {
     type Item = &'a EnemyPosition;
 
@@ -106,4 +179,17 @@ mod test {
         assert_eq!(old_1.distance(curr_1), 3);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test2() {
+        let generic = EnemyAttributes::new();
+        assert_eq!(generic.effective_distance(4), 4);
+        let rammer = EnemyAttributes::of_kind(EnemyKind::Rammer);
+        assert_eq!(rammer.effective_distance(1), 0);
+        assert_eq!(rammer.effective_distance(5), 2);
+        let drone = EnemyAttributes::of_kind(EnemyKind::Drone);
+        assert_eq!(drone.effective_distance(3), 6);
+        let harmless = EnemyAttributes { weight: 0, ..EnemyAttributes::new() };
+        assert_eq!(harmless.effective_distance(0), i32::MAX);
+        assert_eq!(drone.effective_distance(i32::MAX), i32::MAX);
+    }
+}
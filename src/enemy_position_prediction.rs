@@ -1,72 +1,610 @@
-use crate::{borders::Borders, coordinates::Coordinate, enemy_position::EnemyPositions, MAX_NUM_ENEMIES, N};
+use core::num::NonZero;
+
+use crate::{behaviour_classification::{classify, Behaviour}, borders::Borders, coordinates::Coordinate, enemy_history::EnemyHistory, enemy_position::{EnemyAttributes, EnemyPositions}, footprint::Footprint, line::Line, metric::Metric, orientation::Orientation, rng::Rng, robot_position::RobotPosition, threat_map::ticks_to_reach, MAX_NUM_ENEMIES, N};
+
+pub const MIN_BEHAVIOUR_CONFIDENCE: u8 = 50;
 
 #[derive(Debug, Clone, Copy)]
-struct Enemy { pub position: Coordinate, pub direction: Coordinate }
+struct Enemy {
+    pub id: NonZero<u64>,
+    pub position: Coordinate,
+    // cells per tick along each axis, stepped along a Bresenham line
+    pub velocity: Coordinate,
+    pub behaviour: Behaviour,
+    // how far a random walker may have wandered off from `position`
+    pub spread: i32,
+    // cells per tick along the longer axis, the cell by cell steps `move_enemies` takes
+    pub speed: i32,
+    pub attributes: EnemyAttributes,
+}
+
+impl Enemy {
+    fn new(id: NonZero<u64>, position: Coordinate) -> Self {
+        Enemy { id, position, velocity: Coordinate::new(0, 0), behaviour: Behaviour::Bouncer, spread: 0, speed: 1, attributes: EnemyAttributes::new() }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EnemyInteraction {
+    PassThrough, Block, Bounce
+}
 
+#[derive(Debug, Clone, Copy)]
+pub struct InteractionModel {
+    pub enemy_interaction: EnemyInteraction,
+    pub bounce_on_arena_edge: bool,
+}
+
+impl InteractionModel {
+    pub fn none() -> Self {
+        InteractionModel { enemy_interaction: EnemyInteraction::PassThrough, bounce_on_arena_edge: false }
+    }
+}
+
+impl Default for InteractionModel {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+#[derive(Clone)]
 pub struct EnemyPositionPrediction {
     future_positions_mem: [Enemy; MAX_NUM_ENEMIES],
     future_positions_count: usize,
-    borders: Borders
+    borders: Borders,
+    interaction_model: InteractionModel,
+    pursuit_target: Coordinate,
+    metric: Metric,
+    enemy_footprint: Footprint,
 }
 
 impl EnemyPositionPrediction {
     pub fn empty() -> Self {
-        EnemyPositionPrediction { future_positions_mem: [Enemy{position: Coordinate::new(0, 0), direction: Coordinate::new(0, 0)}; MAX_NUM_ENEMIES], future_positions_count: 0, borders: Borders::new() }
+        EnemyPositionPrediction { future_positions_mem: [Enemy::new(NonZero::new(1).unwrap(), Coordinate::new(0, 0)); MAX_NUM_ENEMIES], future_positions_count: 0, borders: Borders::new(), interaction_model: InteractionModel::none(), pursuit_target: Coordinate::new(0, 0), metric: Metric::Manhattan, enemy_footprint: Footprint::single() }
     }
 
     pub fn new(current_positions: &EnemyPositions, previous_positions: &EnemyPositions, borders: Borders) -> Self {
-        let mut future_positions = [Enemy{position: Coordinate::new(0, 0), direction: Coordinate::new(0, 0)}; MAX_NUM_ENEMIES];
+        let mut future_positions = [Enemy::new(NonZero::new(1).unwrap(), Coordinate::new(0, 0)); MAX_NUM_ENEMIES];
         let mut future_positions_count = 0;
 
         for curr_e in current_positions {
-            future_positions[future_positions_count].position = curr_e.position;
+            future_positions[future_positions_count] = Enemy::new(curr_e.id, curr_e.position);
+            future_positions[future_positions_count].attributes = curr_e.attributes;
 
             if let Some(prev_e_position) = previous_positions.get_position_of(curr_e.id) {
-                let direction = (curr_e.position - prev_e_position).normalized();
-                future_positions[future_positions_count].direction = direction;
+                let displacement = curr_e.position - prev_e_position;
+                future_positions[future_positions_count].velocity = displacement;
+                future_positions[future_positions_count].speed = (displacement.x.abs() as i32).max(displacement.y.abs() as i32).max(1);
             } else if curr_e.position.is_corner() { // enemies in the corner are assumed to stand still
-                future_positions[future_positions_count].direction = Coordinate::new(0, 0);
+                future_positions[future_positions_count].velocity = Coordinate::new(0, 0);
             } else if curr_e.position.y == -(N as i8)/2 { // assume that enemies at the border will go towards the center.
-                future_positions[future_positions_count].direction = Coordinate::new(0, 1);
+                future_positions[future_positions_count].velocity = Coordinate::new(0, 1);
             } else if curr_e.position.y == (N as i8)/2 { 
-                future_positions[future_positions_count].direction = Coordinate::new(0, -1);
+                future_positions[future_positions_count].velocity = Coordinate::new(0, -1);
             } else if curr_e.position.x == -(N as i8)/2 {
-                future_positions[future_positions_count].direction = Coordinate::new(1, 0);
+                future_positions[future_positions_count].velocity = Coordinate::new(1, 0);
             } else if curr_e.position.x == (N as i8)/2 {
-                future_positions[future_positions_count].direction = Coordinate::new(-1, 0);
+                future_positions[future_positions_count].velocity = Coordinate::new(-1, 0);
+            }
+            if previous_positions.get_position_of(curr_e.id).is_none() {
+                // without a previous observation the enemy moves as fast as its kind does
+                let enemy = &mut future_positions[future_positions_count];
+                enemy.speed = curr_e.attributes.speed.max(1);
+                enemy.velocity = Coordinate::new(enemy.velocity.x * enemy.speed as i8, enemy.velocity.y * enemy.speed as i8);
             }
 
             future_positions_count += 1
         }
         
-        EnemyPositionPrediction{ future_positions_mem: future_positions, future_positions_count, borders }
+        EnemyPositionPrediction{ future_positions_mem: future_positions, future_positions_count, borders, interaction_model: InteractionModel::none(), pursuit_target: Coordinate::new(0, 0), metric: Metric::Manhattan, enemy_footprint: Footprint::single() }
+    }
+
+    pub fn use_interaction_model(&mut self, interaction_model: InteractionModel) {
+        self.interaction_model = interaction_model
+    }
+
+    // how `min_distance_from` measures distances, Manhattan by default
+    pub fn use_metric(&mut self, metric: Metric) {
+        self.metric = metric
+    }
+
+    // the cells every enemy covers, facing north
+    pub fn use_enemy_footprint(&mut self, enemy_footprint: Footprint) {
+        self.enemy_footprint = enemy_footprint
+    }
+
+    // the cell pursuers are heading for, usually the robot
+    pub fn use_pursuit_target(&mut self, pursuit_target: Coordinate) {
+        self.pursuit_target = pursuit_target
+    }
+
+    // Switches the motion model of every enemy whose behaviour could be classified confidently
+    // from its history. The other enemies keep the straight line bouncing model.
+    pub fn use_behaviour_models(&mut self, history: &EnemyHistory) {
+        for enemy in &mut self.future_positions_mem[..self.future_positions_count] {
+            let Some(classification) = history.track_of(enemy.id).and_then(classify) else {
+                continue;
+            };
+            if classification.confidence < MIN_BEHAVIOUR_CONFIDENCE {
+                continue;
+            }
+            enemy.behaviour = classification.behaviour;
+            if enemy.behaviour == Behaviour::Stationary {
+                enemy.velocity = Coordinate::new(0, 0);
+            }
+        }
+    }
+
+    // Replaces the speed seen in the last two observations by the average speed over each enemy's history.
+    pub fn use_speed_estimates(&mut self, history: &EnemyHistory) {
+        for enemy in &mut self.future_positions_mem[..self.future_positions_count] {
+            if let Some(speed) = history.track_of(enemy.id).and_then(|t| t.estimated_speed(Metric::Chebyshev)) {
+                let speed = speed.max(1);
+                // keep the heading, but cover `speed` cells along the longer axis
+                let old_speed = enemy.speed;
+                enemy.velocity = Coordinate::new((enemy.velocity.x as i32 * speed / old_speed) as i8, (enemy.velocity.y as i32 * speed / old_speed) as i8);
+                enemy.speed = speed;
+            }
+        }
     }
 
     fn future_positions(&self) -> &[Enemy] {
         &self.future_positions_mem[..self.future_positions_count]
     }
 
+    fn is_blocked(&self, coord: Coordinate) -> bool {
+        self.enemy_footprint.cells(coord, Orientation::North).any(|c| {
+            self.borders.is_border(c) || (self.interaction_model.bounce_on_arena_edge && c.to_index().is_none())
+        })
+    }
+
+    // Moves every enemy by one tick in a random direction or not at all, ignoring the motion models.
+    // Used to sample what enemies might do rather than what they most likely do.
+    pub fn move_enemies_randomly(&mut self, rng: &mut Rng) {
+        for i in 0..self.future_positions_count {
+            let enemy = self.future_positions_mem[i];
+            let mut position = enemy.position;
+            for _ in 0..enemy.speed {
+                let step = match rng.below(5) {
+                    0 => Coordinate::new(0, -1),
+                    1 => Coordinate::new(1, 0),
+                    2 => Coordinate::new(0, 1),
+                    3 => Coordinate::new(-1, 0),
+                    _ => Coordinate::new(0, 0),
+                };
+                if !self.is_blocked(position + step) && (position + step).to_index().is_some() {
+                    position += step;
+                }
+            }
+            self.future_positions_mem[i].position = position;
+        }
+    }
+
+    // Moves every enemy by one tick straight at `target`, the worst the enemies can do to a robot
+    // standing there. Ignores the motion models like `move_enemies_randomly`.
+    pub fn move_enemies_towards(&mut self, target: Coordinate) {
+        for i in 0..self.future_positions_count {
+            let enemy = self.future_positions_mem[i];
+            let mut position = enemy.position;
+            for _ in 0..enemy.speed {
+                let step = (target - position).normalized();
+                if !self.is_blocked(position + step) && (position + step).to_index().is_some() {
+                    position += step;
+                }
+            }
+            self.future_positions_mem[i].position = position;
+        }
+    }
+
+    // the predicted cell of every enemy
+    pub fn positions(&self) -> impl Iterator<Item = Coordinate> + '_ {
+        self.future_positions().iter().map(|e| e.position)
+    }
+
+    // Moves every enemy by one tick. Fast enemies take their cells one at a time, so they bounce
+    // on borders and collide with other enemies instead of jumping over them.
     pub fn move_enemies(&mut self) {
-        for enemy in &mut self.future_positions_mem[..self.future_positions_count] {
-            // enemies bounce on borders
-            if self.borders.is_border(enemy.position + enemy.direction) {
-                enemy.direction = -enemy.direction;
+        let max_speed = self.future_positions().iter().map(|e| e.speed).max().unwrap_or(0);
+        for step in 0..max_speed {
+            self.step_enemies(step);
+        }
+    }
+
+    fn step_enemies(&mut self, step: i32) {
+        let count = self.future_positions_count;
+        let mut targets = [Coordinate::new(0, 0); MAX_NUM_ENEMIES];
+
+        for (i, target) in targets[..count].iter_mut().enumerate() {
+            let mut enemy = self.future_positions_mem[i];
+            if enemy.speed <= step {
+                // done for this tick
+                *target = enemy.position;
+                continue;
+            }
+            match enemy.behaviour {
+                Behaviour::Bouncer => {}
+                Behaviour::Stationary => enemy.velocity = Coordinate::new(0, 0),
+                Behaviour::Pursuer => {
+                    let heading = (self.pursuit_target - enemy.position).normalized();
+                    enemy.velocity = Coordinate::new(heading.x * enemy.speed as i8, heading.y * enemy.speed as i8);
+                }
+                Behaviour::RandomWalker => {
+                    enemy.velocity = Coordinate::new(0, 0);
+                    enemy.spread += 1;
+                }
             }
-            enemy.position += enemy.direction
+            let offset = step_along(enemy.velocity, step);
+            let offset = self.bounce(&mut enemy, offset);
+            *target = if self.is_blocked(enemy.position + offset) {
+                // boxed in on both sides
+                enemy.position
+            } else {
+                enemy.position + offset
+            };
+            self.future_positions_mem[i] = enemy;
         }
+
+        if self.interaction_model.enemy_interaction != EnemyInteraction::PassThrough {
+            self.resolve_collisions(&mut targets[..count]);
+        }
+
+        for (enemy, &target) in self.future_positions_mem[..count].iter_mut().zip(targets.iter()) {
+            enemy.position = target;
+        }
+    }
+
+    // Enemies bounce on borders by reflecting the axis that is blocked. A diagonal step into the tip
+    // of a corner, where neither axis is blocked on its own, reflects both.
+    fn bounce(&self, enemy: &mut Enemy, mut offset: Coordinate) -> Coordinate {
+        if !self.is_blocked(enemy.position + offset) {
+            return offset;
+        }
+        let blocked_x = offset.x != 0 && self.is_blocked(enemy.position + Coordinate::new(offset.x, 0));
+        let blocked_y = offset.y != 0 && self.is_blocked(enemy.position + Coordinate::new(0, offset.y));
+        let (reflect_x, reflect_y) = if blocked_x || blocked_y { (blocked_x, blocked_y) } else { (true, true) };
+        if reflect_x {
+            offset.x = -offset.x;
+            enemy.velocity.x = -enemy.velocity.x;
+        }
+        if reflect_y {
+            offset.y = -offset.y;
+            enemy.velocity.y = -enemy.velocity.y;
+        }
+        offset
+    }
+
+    // Enemies that would end up in the same cell, swap cells, or walk into an enemy that does not move
+    // stay where they are. Stopping one enemy can cause a new collision, so repeat until nothing changes.
+    fn resolve_collisions(&mut self, targets: &mut [Coordinate]) {
+        loop {
+            let mut collides = [false; MAX_NUM_ENEMIES];
+            for i in 0..targets.len() {
+                let position = self.future_positions_mem[i].position;
+                if targets[i] == position {
+                    continue;
+                }
+                let overlaps = |a, b| self.enemy_footprint.overlaps(a, b);
+                collides[i] = (0..targets.len()).filter(|&j| j != i).any(|j| {
+                    let other_position = self.future_positions_mem[j].position;
+                    overlaps(targets[i], targets[j]) ||
+                    (overlaps(targets[i], other_position) && (overlaps(targets[j], position) || targets[j] == other_position))
+                });
+            }
+            if !collides.contains(&true) {
+                return;
+            }
+
+            for (i, target) in targets.iter_mut().enumerate() {
+                if collides[i] {
+                    let enemy = &mut self.future_positions_mem[i];
+                    *target = enemy.position;
+                    if self.interaction_model.enemy_interaction == EnemyInteraction::Bounce {
+                        enemy.velocity = -enemy.velocity;
+                    }
+                }
+            }
+        }
+    }
+
+    // the distance from `from` to the closest cell of the enemy, less the spread of a random walker,
+    // both measured in the metric
+    fn distance_to(&self, enemy: &Enemy, from: Coordinate) -> i32 {
+        let spread = self.metric.distance(Coordinate::new(0, 0), Coordinate::new(enemy.spread.min(i8::MAX as i32) as i8, 0));
+        let distance = self.enemy_footprint.cells(enemy.position, Orientation::North).map(|c| self.metric.distance(c, from)).min().unwrap_or(i32::MAX);
+        (distance - spread).max(0)
     }
 
+    // How far the enemy gets in a tick, measured in the metric like the distances it is divided into.
+    // A diagonal step covers two cells in Manhattan distance.
+    fn distance_per_tick(&self, enemy: &Enemy) -> i32 {
+        let step = if enemy.velocity == Coordinate::new(0, 0) { Coordinate::new(enemy.speed as i8, 0) } else { enemy.velocity };
+        self.metric.distance(Coordinate::new(0, 0), step).max(1)
+    }
+
+    // like `min_distance_from`, but counted in ticks the enemies need to get within their danger radius
+    pub fn min_ticks_from(&self, from: Coordinate) -> i32 {
+        self.future_positions().iter().map(|enemy| {
+            let distance = (self.distance_to(enemy, from) - enemy.attributes.danger_radius).max(0);
+            ticks_to_reach(distance, self.distance_per_tick(enemy))
+        }).min().unwrap_or(0)
+    }
+
+    // the distance to the most threatening enemy, each enemy counted by its attributes
     pub fn min_distance_from(&self, from: Coordinate) -> i32 {
-        self.future_positions().iter().map(|enemy| enemy.position.distance(from)).min().unwrap_or(0)
+        self.future_positions().iter().map(|enemy| enemy.attributes.effective_distance(self.distance_to(enemy, from))).min().unwrap_or(0)
+    }
+
+    // the distance to the closest enemy from any cell of a robot with the given footprint
+    pub fn min_distance_from_robot(&self, robot_position: &RobotPosition, footprint: &Footprint) -> i32 {
+        footprint.robot_cells(robot_position).map(|c| self.min_distance_from(c)).min().unwrap_or(0)
+    }
+
+    // the distance from the closest cell of the robot to every enemy, in the order of `future_positions`
+    pub fn distances_from_robot<'a>(&'a self, robot_position: &'a RobotPosition, footprint: &'a Footprint) -> impl Iterator<Item = i32> + 'a {
+        self.future_positions().iter().map(move |enemy| {
+            footprint.robot_cells(robot_position).map(|c| enemy.attributes.effective_distance(self.distance_to(enemy, c))).min().unwrap_or(i32::MAX)
+        })
+    }
+}
+
+// the unit step number `step` of a tick along the Bresenham line of `velocity`
+fn step_along(velocity: Coordinate, step: i32) -> Coordinate {
+    let mut line = Line::new(Coordinate::new(0, 0), velocity);
+    match (line.nth(step as usize), line.next()) {
+        (Some(from), Some(to)) => to - from,
+        _ => Coordinate::new(0, 0),
     }
 }
 
 #[cfg(test)]
 mod prediction_tests {
+    use core::num::NonZero;
+
+    use crate::enemy_position::{EnemyKind, EnemyPosition};
+    use crate::HISTORY_LENGTH;
+
     use super::*;
 
     #[test]
     fn test1() {
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(0, 0)));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(1, 0)));
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(3, 0));
+
+        let mut prediction = EnemyPositionPrediction::new(&current, &previous, borders);
+        assert_eq!(prediction.min_distance_from(Coordinate::new(1, 0)), 0);
+
+        prediction.move_enemies();
+        assert_eq!(prediction.min_distance_from(Coordinate::new(2, 0)), 0);
+
+        // the next step would hit the border, so the enemy bounces back
+        prediction.move_enemies();
+        assert_eq!(prediction.min_distance_from(Coordinate::new(1, 0)), 0);
+    }
+
+    fn head_on(interaction_model: InteractionModel) -> EnemyPositionPrediction {
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-3, 0)));
+        previous.push(EnemyPosition::new(NonZero::new(2).unwrap(), Coordinate::new(3, 0)));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-2, 0)));
+        current.push(EnemyPosition::new(NonZero::new(2).unwrap(), Coordinate::new(2, 0)));
+
+        let mut prediction = EnemyPositionPrediction::new(&current, &previous, Borders::new());
+        prediction.use_interaction_model(interaction_model);
+        prediction
+    }
+
+    fn positions(prediction: &EnemyPositionPrediction) -> [Coordinate; 2] {
+        [prediction.future_positions()[0].position, prediction.future_positions()[1].position]
+    }
+
+    #[test]
+    fn test2() {
+        let mut prediction = head_on(InteractionModel::none());
+        for _ in 0..3 {
+            prediction.move_enemies();
+        }
+        assert_eq!(positions(&prediction), [Coordinate::new(1, 0), Coordinate::new(-1, 0)]);
+
+        let mut prediction = head_on(InteractionModel { enemy_interaction: EnemyInteraction::Block, bounce_on_arena_edge: false });
+        for _ in 0..3 {
+            prediction.move_enemies();
+        }
+        assert_eq!(positions(&prediction), [Coordinate::new(-1, 0), Coordinate::new(1, 0)]);
+
+        let mut prediction = head_on(InteractionModel { enemy_interaction: EnemyInteraction::Bounce, bounce_on_arena_edge: false });
+        for _ in 0..3 {
+            prediction.move_enemies();
+        }
+        assert_eq!(positions(&prediction), [Coordinate::new(-2, 0), Coordinate::new(2, 0)]);
+    }
+
+    #[test]
+    fn test3() {
+        let mut previous = EnemyPositions::new();
+        let half = (N / 2) as i8;
+        previous.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(half - 1, 1)));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(half, 1)));
+
+        let mut prediction = EnemyPositionPrediction::new(&current, &previous, Borders::new());
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(half + 1, 1));
+
+        let mut prediction = EnemyPositionPrediction::new(&current, &previous, Borders::new());
+        prediction.use_interaction_model(InteractionModel { enemy_interaction: EnemyInteraction::PassThrough, bounce_on_arena_edge: true });
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(half - 1, 1));
+    }
+
+    #[test]
+    fn test4() {
+        let mut history = EnemyHistory::new();
+        let mut current = EnemyPositions::new();
+        for tick in 0..HISTORY_LENGTH as i8 {
+            current.clear();
+            current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-4, -4)));
+            current.push(EnemyPosition::new(NonZero::new(2).unwrap(), Coordinate::new(4, 4 - tick)));
+            current.push(EnemyPosition::new(NonZero::new(3).unwrap(), Coordinate::new(tick % 2, tick / 2)));
+            history.record(&current, Coordinate::new(0, -4));
+        }
+
+        let mut prediction = EnemyPositionPrediction::new(&current, &EnemyPositions::new(), Borders::new());
+        prediction.use_pursuit_target(Coordinate::new(0, -4));
+        prediction.use_behaviour_models(&history);
+        assert_eq!(prediction.future_positions()[0].behaviour, Behaviour::Stationary);
+        assert_eq!(prediction.future_positions()[1].behaviour, Behaviour::Pursuer);
+        assert_eq!(prediction.future_positions()[2].behaviour, Behaviour::RandomWalker);
+
+        prediction.move_enemies();
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(-4, -4));
+        // the pursuer closes in on the target, the random walker could be anywhere within two cells
+        assert_eq!(prediction.future_positions()[1].position.distance(Coordinate::new(0, -4)), 3);
+        assert_eq!(prediction.min_distance_from(Coordinate::new(1, 1)), 0);
+        assert_eq!(prediction.min_distance_from(Coordinate::new(-2, 2)), 2);
+    }
+
+    #[test]
+    fn test5() {
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(0, 0)));
+        let mut prediction = EnemyPositionPrediction::new(&current, &EnemyPositions::new(), Borders::new());
+        assert_eq!(prediction.min_distance_from(Coordinate::new(2, 2)), 4);
+        prediction.use_metric(Metric::Chebyshev);
+        assert_eq!(prediction.min_distance_from(Coordinate::new(2, 2)), 2);
+        prediction.use_metric(Metric::Octile);
+        assert_eq!(prediction.min_distance_from(Coordinate::new(2, 2)), 3);
+    }
+
+    #[test]
+    fn test6() {
+        let id = NonZero::new(1).unwrap();
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(id, Coordinate::new(-4, 0)));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(id, Coordinate::new(-2, 0)));
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(0, 0));
+
+        let mut prediction = EnemyPositionPrediction::new(&current, &previous, borders);
+        assert_eq!(prediction.min_ticks_from(Coordinate::new(-2, 4)), 2);
+        assert_eq!(prediction.min_distance_from(Coordinate::new(-2, 4)), 4);
+
+        // the second cell of the tick is the border, so the enemy turns around instead of passing it
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(-2, 0));
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(-4, 0));
+
+        let mut history = EnemyHistory::new();
+        for x in [-4, -3, -2] {
+            let mut enemies = EnemyPositions::new();
+            enemies.push(EnemyPosition::new(id, Coordinate::new(x, 0)));
+            history.record(&enemies, Coordinate::new(0, 0));
+        }
+        let mut prediction = EnemyPositionPrediction::new(&current, &previous, Borders::new());
+        prediction.use_speed_estimates(&history);
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(-1, 0));
+
+        // one diagonal cell per tick closes in two cells of Manhattan distance per tick
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(id, Coordinate::new(-3, -1)));
+        let mut prediction = EnemyPositionPrediction::new(&current, &previous, Borders::new());
+        assert_eq!(prediction.min_ticks_from(Coordinate::new(4, 0)), 3);
+        prediction.use_metric(Metric::Chebyshev);
+        assert_eq!(prediction.min_ticks_from(Coordinate::new(4, 0)), 6);
+    }
+
+    fn single(previous_position: Coordinate, current_position: Coordinate, borders: Borders) -> EnemyPositionPrediction {
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(NonZero::new(1).unwrap(), previous_position));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), current_position));
+        EnemyPositionPrediction::new(&current, &previous, borders)
+    }
 
+    #[test]
+    fn test7() {
+        let mut prediction = single(Coordinate::new(-1, -1), Coordinate::new(0, 0), Borders::new());
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(1, 1));
+
+        // two cells right and one down per tick
+        let mut prediction = single(Coordinate::new(-4, -2), Coordinate::new(-2, -1), Borders::new());
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(0, 0));
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(2, 1));
+    }
+
+    #[test]
+    fn test8() {
+        // a diagonal mover hitting a wall only turns around along the x axis
+        let mut wall = Borders::new();
+        for y in -4..=4 {
+            wall.set_border(Coordinate::new(2, y));
+        }
+        let mut prediction = single(Coordinate::new(0, -1), Coordinate::new(1, 0), wall);
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(0, 1));
+        assert_eq!(prediction.future_positions()[0].velocity, Coordinate::new(-1, 1));
+
+        // straight into the tip of a corner it comes back the way it came
+        let mut corner = Borders::new();
+        corner.set_border(Coordinate::new(2, 1));
+        let mut prediction = single(Coordinate::new(0, -1), Coordinate::new(1, 0), corner);
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(0, -1));
+    }
+
+    #[test]
+    fn test9() {
+        // two wide enemies side by side, the right one standing still
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-3, 0)));
+        previous.push(EnemyPosition::new(NonZero::new(2).unwrap(), Coordinate::new(0, 0)));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-2, 0)));
+        current.push(EnemyPosition::new(NonZero::new(2).unwrap(), Coordinate::new(0, 0)));
+        let mut prediction = EnemyPositionPrediction::new(&current, &previous, Borders::new());
+        prediction.use_enemy_footprint(Footprint::rectangle(2, 1));
+        prediction.use_interaction_model(InteractionModel { enemy_interaction: EnemyInteraction::Block, bounce_on_arena_edge: false });
+
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(-2, 0));
+        assert_eq!(prediction.min_distance_from(Coordinate::new(1, 2)), 2);
+
+        let robot_position = RobotPosition { position: Coordinate::new(2, 1), orientation: Orientation::North };
+        assert_eq!(prediction.min_distance_from_robot(&robot_position, &Footprint::single()), 2);
+        assert_eq!(prediction.min_distance_from_robot(&robot_position, &Footprint::rectangle(1, 2)), 2);
+        let robot_position = RobotPosition { position: Coordinate::new(3, 0), orientation: Orientation::South };
+        assert_eq!(prediction.min_distance_from_robot(&robot_position, &Footprint::rectangle(2, 1)), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test10() {
+        // the rammer stands on the west edge, `centre` is four cells east of it
+        let half = (N / 2) as i8;
+        let centre = Coordinate::new(-half + 4, 0);
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::with_attributes(NonZero::new(1).unwrap(), Coordinate::new(-half, 0), EnemyAttributes::of_kind(EnemyKind::Rammer)));
+        current.push(EnemyPosition::with_attributes(NonZero::new(2).unwrap(), Coordinate::new(-half + 6, 0), EnemyAttributes::of_kind(EnemyKind::Drone)));
+        let mut prediction = EnemyPositionPrediction::new(&current, &EnemyPositions::new(), Borders::new());
+
+        // the drone is closer, but the rammer is the bigger threat
+        assert_eq!(prediction.min_distance_from(centre), 1);
+        assert_eq!(prediction.min_ticks_from(centre), 2);
+
+        // the rammer enters from the edge at its own speed
+        prediction.move_enemies();
+        assert_eq!(prediction.future_positions()[0].position, Coordinate::new(-half + 2, 0));
+        assert_eq!(prediction.future_positions()[1].position, Coordinate::new(-half + 6, 0));
+        assert_eq!(prediction.min_distance_from(centre), 0);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{borders::Borders, coordinates::Coordinate, enemy_position_prediction::EnemyPositionPrediction, footprint::Footprint, metric::{Connectivity, Metric}, robot_position::RobotPosition, threat_cell::ThreatCell, threat_map::ThreatMap, MAX_NUM_ENEMIES, N};
+
+// `InverseDistanceSum` adds this much for an enemy one cell away
+pub const INVERSE_DISTANCE_SCALE: i32 = 1000;
+
+// The measures an evaluation can weigh. Each one is measured from the robot's body, a positive
+// weight rewards a high value and a negative one punishes it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Term {
+    // the distance to the closest enemy
+    MinEnemyDistance,
+    // INVERSE_DISTANCE_SCALE / distance summed over all enemies, high when several are close
+    InverseDistanceSum,
+    // the distance to the closest border cell or to the outside of the arena
+    WallDistance,
+    // how many of the four neighbouring places the robot fits in
+    FreeNeighbours,
+    // the distance from the centre of the arena
+    CentreDistance,
+    // the threat map value under the robot, 0 without a threat map
+    Threat,
+}
+
+pub const TERM_COUNT: usize = 6;
+pub const TERMS: [Term; TERM_COUNT] = [Term::MinEnemyDistance, Term::InverseDistanceSum, Term::WallDistance, Term::FreeNeighbours, Term::CentreDistance, Term::Threat];
+
+// What a position is evaluated against.
+#[derive(Clone, Copy)]
+pub struct Scene<'a, T: ThreatCell = i32> {
+    pub prediction: &'a EnemyPositionPrediction,
+    pub borders: &'a Borders,
+    pub threat_map: Option<&'a ThreatMap<T>>,
+}
+
+impl<'a> Scene<'a> {
+    pub fn new(prediction: &'a EnemyPositionPrediction, borders: &'a Borders) -> Self {
+        Scene { prediction, borders, threat_map: None }
+    }
+}
+
+impl<'a, T: ThreatCell> Scene<'a, T> {
+    pub fn with_threat_map<U: ThreatCell>(&self, threat_map: &'a ThreatMap<U>) -> Scene<'a, U> {
+        Scene { prediction: self.prediction, borders: self.borders, threat_map: Some(threat_map) }
+    }
+}
+
+impl Term {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Term::MinEnemyDistance => "MinEnemyDistance",
+            Term::InverseDistanceSum => "InverseDistanceSum",
+            Term::WallDistance => "WallDistance",
+            Term::FreeNeighbours => "FreeNeighbours",
+            Term::CentreDistance => "CentreDistance",
+            Term::Threat => "Threat",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Term> {
+        TERMS.into_iter().find(|t| t.name() == name)
+    }
+
+    pub fn value<T: ThreatCell>(&self, robot_position: &RobotPosition, footprint: &Footprint, scene: &Scene<T>) -> i32 {
+        match self {
+            Term::MinEnemyDistance => scene.prediction.min_distance_from_robot(robot_position, footprint),
+            Term::InverseDistanceSum => scene.prediction.distances_from_robot(robot_position, footprint)
+                .map(|d| INVERSE_DISTANCE_SCALE / d.max(1))
+                .fold(0, i32::saturating_add),
+            Term::WallDistance => footprint.robot_cells(robot_position).map(|c| wall_distance(c, scene.borders)).min().unwrap_or(0),
+            Term::FreeNeighbours => robot_position.position.neighbours(Connectivity::Four).filter(|&p| {
+                footprint.is_inside_arena(p, robot_position.orientation) && !footprint.collides_with(p, robot_position.orientation, scene.borders)
+            }).count() as i32,
+            Term::CentreDistance => Metric::Manhattan.distance(robot_position.position, Coordinate::new(0, 0)),
+            Term::Threat => scene.threat_map.map(|m| footprint.threat_at(robot_position.position, robot_position.orientation, m)).unwrap_or(0),
+        }
+    }
+}
+
+impl Term {
+    // The lowest and highest value of the term in the arena, for enemies of the generic kind measured
+    // in Manhattan distance. Other values, like a harmless enemy that counts as infinitely far away,
+    // are clamped to it by `Evaluation::normalized`.
+    pub fn range(&self) -> (i32, i32) {
+        let span = 2 * (N as i32 - 1);
+        match self {
+            Term::MinEnemyDistance | Term::Threat => (0, span),
+            Term::InverseDistanceSum => (0, MAX_NUM_ENEMIES as i32 * INVERSE_DISTANCE_SCALE),
+            Term::WallDistance => (0, (N / 2) as i32 + 1),
+            Term::FreeNeighbours => (0, 4),
+            Term::CentreDistance => (0, 2 * (N / 2) as i32),
+        }
+    }
+}
+
+// the Manhattan distance to the closest border cell or cell outside of the arena
+fn wall_distance(coord: Coordinate, borders: &Borders) -> i32 {
+    let half = (N / 2) as i32;
+    let outside = (half - (coord.x as i32).abs()).min(half - (coord.y as i32).abs()) + 1;
+    borders.iter().map(|b| Metric::Manhattan.distance(coord, b)).fold(outside, i32::min)
+}
+
+// A weighted sum of terms that planners maximise. Weights can be changed at any time.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Evaluation {
+    weights: [i32; TERM_COUNT],
+}
+
+impl Evaluation {
+    pub fn empty() -> Self {
+        Evaluation { weights: [0; TERM_COUNT] }
+    }
+
+    // one weight per term, in the order of TERMS
+    pub const fn from_weights(weights: [i32; TERM_COUNT]) -> Self {
+        Evaluation { weights }
+    }
+
+    pub fn weights(&self) -> [i32; TERM_COUNT] {
+        self.weights
+    }
+
+    // only the distance to the closest enemy, what the planners have always maximised
+    pub fn new() -> Self {
+        let mut evaluation = Self::empty();
+        evaluation.use_weight(Term::MinEnemyDistance, 1);
+        evaluation
+    }
+
+    pub fn use_weight(&mut self, term: Term, weight: i32) {
+        self.weights[term as usize] = weight
+    }
+
+    pub fn weight(&self, term: Term) -> i32 {
+        self.weights[term as usize]
+    }
+
+    // the terms with a weight other than 0
+    pub fn terms(&self) -> impl Iterator<Item = (Term, i32)> + '_ {
+        TERMS.iter().map(|&t| (t, self.weight(t))).filter(|&(_, w)| w != 0)
+    }
+
+    pub fn evaluate<T: ThreatCell>(&self, robot_position: &RobotPosition, footprint: &Footprint, scene: &Scene<T>) -> i64 {
+        self.terms().map(|(term, weight)| weight as i64 * term.value(robot_position, footprint, scene) as i64).sum()
+    }
+
+    // The evaluation mapped onto 0..=scale, for planners that add it to rewards of their own. Every
+    // term is clamped to its range and counts from 0 at its worst value to its weight at its best,
+    // the worst being the highest value for a negative weight.
+    pub fn normalized<T: ThreatCell>(&self, robot_position: &RobotPosition, footprint: &Footprint, scene: &Scene<T>, scale: i64) -> i64 {
+        let total_weight: i64 = self.terms().map(|(_, weight)| (weight as i64).abs()).sum();
+        if total_weight == 0 {
+            return 0;
+        }
+        let sum: i64 = self.terms().map(|(term, weight)| {
+            let (low, high) = term.range();
+            let value = term.value(robot_position, footprint, scene).clamp(low, high);
+            let goodness = if weight > 0 { value - low } else { high - value } as i64;
+            (weight as i64).abs() * goodness * scale / (high - low) as i64
+        }).sum();
+        sum / total_weight
+    }
+}
+
+impl Default for Evaluation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod evaluation_tests {
+    use core::num::NonZero;
+
+    use crate::enemy_position::{EnemyPosition, EnemyPositions};
+    use crate::orientation::Orientation;
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(3, 0)));
+        enemies.push(EnemyPosition::new(NonZero::new(2).unwrap(), Coordinate::new(0, 2)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(-1, 0));
+        let scene = Scene::new(&prediction, &borders);
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let single = Footprint::single();
+
+        assert_eq!(Term::MinEnemyDistance.value(&robot_position, &single, &scene), 2);
+        assert_eq!(Term::InverseDistanceSum.value(&robot_position, &single, &scene), 1000 / 3 + 1000 / 2);
+        assert_eq!(Term::WallDistance.value(&robot_position, &single, &scene), 1);
+        assert_eq!(Term::FreeNeighbours.value(&robot_position, &single, &scene), 3);
+        assert_eq!(Term::CentreDistance.value(&robot_position, &single, &scene), 0);
+        assert_eq!(Term::Threat.value(&robot_position, &single, &scene), 0);
+
+        // in a corner the arena is the closest wall and only two neighbours are left
+        let half = (N / 2) as i8;
+        let corner = RobotPosition { position: Coordinate::new(half, half), orientation: Orientation::North };
+        assert_eq!(Term::WallDistance.value(&corner, &single, &scene), 1);
+        assert_eq!(Term::FreeNeighbours.value(&corner, &single, &scene), 2);
+        assert_eq!(Term::CentreDistance.value(&corner, &single, &scene), 2 * half as i32);
+
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate(&[Coordinate::new(3, 0)]);
+        assert_eq!(Term::Threat.value(&robot_position, &single, &scene.with_threat_map(&threat_map)), 3);
+    }
+
+    #[test]
+    fn test2() {
+        let half = (N / 2) as i8;
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-half, -half)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+        let borders = Borders::new();
+        let scene = Scene::new(&prediction, &borders);
+        let centre = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let corner = RobotPosition { position: Coordinate::new(half, half), orientation: Orientation::North };
+        let single = Footprint::single();
+
+        // the far corner is farthest from the enemy, but a centre preference outweighs that
+        let mut evaluation = Evaluation::new();
+        assert!(evaluation.evaluate(&corner, &single, &scene) > evaluation.evaluate(&centre, &single, &scene));
+        evaluation.use_weight(Term::CentreDistance, -2);
+        evaluation.use_weight(Term::FreeNeighbours, 1);
+        assert_eq!(evaluation.terms().count(), 3);
+        assert_eq!(evaluation.evaluate(&centre, &single, &scene), 2 * half as i64 + 4);
+        assert_eq!(evaluation.evaluate(&corner, &single, &scene), 2);
+
+        assert_eq!(Evaluation::empty().evaluate(&corner, &single, &scene), 0);
+        assert_eq!(Evaluation::default(), Evaluation::new());
+        assert_eq!(Evaluation::from_weights(evaluation.weights()), evaluation);
+        assert_eq!(Term::from_name(Term::CentreDistance.name()), Some(Term::CentreDistance));
+        assert_eq!(Term::from_name("Nothing"), None);
+    }
+
+    #[test]
+    fn test3() {
+        let half = (N / 2) as i8;
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-half, -half)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+        let borders = Borders::new();
+        let scene = Scene::new(&prediction, &borders);
+        let centre = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let corner = RobotPosition { position: Coordinate::new(half, half), orientation: Orientation::North };
+        let caught = RobotPosition { position: Coordinate::new(-half, -half), orientation: Orientation::North };
+        let single = Footprint::single();
+
+        // the far corner is as far from the enemy as it gets, halfway there is half the scale
+        let mut evaluation = Evaluation::new();
+        assert_eq!(evaluation.normalized(&corner, &single, &scene, 1000), 1000);
+        assert_eq!(evaluation.normalized(&centre, &single, &scene, 1000), 500);
+        assert_eq!(evaluation.normalized(&caught, &single, &scene, 1000), 0);
+
+        // a negative weight counts the lowest value as the best
+        evaluation.use_weight(Term::CentreDistance, -2);
+        assert_eq!(evaluation.normalized(&centre, &single, &scene, 1000), (500 + 2 * 1000) / 3);
+        assert_eq!(evaluation.normalized(&corner, &single, &scene, 1000), 1000 / 3);
+        assert_eq!(Evaluation::empty().normalized(&corner, &single, &scene, 1000), 0);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{coordinates::Coordinate, direction::Direction, goal_navigation::{goal_directed_next_move, NavigationCosts}, occupancy::{Occupancy, OccupancyMap}, orientation::Orientation, robot_position::RobotPosition, threat_cell::ThreatCell, threat_map::ThreatMap, N};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExplorationCosts {
+    pub navigation: NavigationCosts,
+    // frontiers closer than this to an enemy are not explored
+    pub min_threat_distance: i32,
+}
+
+impl ExplorationCosts {
+    pub fn new() -> Self {
+        ExplorationCosts { navigation: NavigationCosts::new(), min_threat_distance: 2 }
+    }
+}
+
+impl Default for ExplorationCosts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// breadth first search over the known free cells, starting at the robot
+fn free_space_distances(start: Coordinate, occupancy: &OccupancyMap) -> [i32; N * N] {
+    let mut distances = [i32::MAX; N * N];
+    let mut queue = [0usize; N * N];
+    let (mut head, mut tail) = (0, 0);
+
+    let Some(start_index) = start.to_index() else {
+        return distances;
+    };
+    distances[start_index] = 0;
+    queue[tail] = start_index;
+    tail += 1;
+
+    while head < tail {
+        let index = queue[head];
+        head += 1;
+        let coord = Coordinate::from_index(index).unwrap();
+        for direction in [Direction::Front, Direction::Right, Direction::Back, Direction::Left] {
+            let next = coord.in_direction(direction, Orientation::North);
+            if occupancy.at(next) != Occupancy::Free {
+                continue;
+            }
+            let next_index = next.to_index().unwrap();
+            if distances[next_index] == i32::MAX {
+                distances[next_index] = distances[index] + 1;
+                queue[tail] = next_index;
+                tail += 1;
+            }
+        }
+    }
+    distances
+}
+
+fn nearest_frontier<T: ThreatCell>(robot_position: &RobotPosition, occupancy: &OccupancyMap, threat_map: &ThreatMap<T>, costs: &ExplorationCosts) -> Option<Coordinate> {
+    let distances = free_space_distances(robot_position.position, occupancy);
+
+    let mut best = None;
+    let mut best_score = i32::MAX;
+    for (index, &distance) in distances.iter().enumerate() {
+        if distance == i32::MAX {
+            continue;
+        }
+        let coord = Coordinate::from_index(index).unwrap();
+        let unknown_neighbours = occupancy.unknown_neighbours(coord) as i32;
+        if unknown_neighbours == 0 || threat_map.distance_at(coord) < costs.min_threat_distance {
+            continue;
+        }
+        // prefer close frontiers, and among those the ones that reveal the most cells
+        let score = distance * 4 - unknown_neighbours;
+        if score < best_score {
+            best = Some(coord);
+            best_score = score;
+        }
+    }
+    best
+}
+
+// Drives the robot towards the nearest frontier between known free space and unknown cells.
+// Once on a frontier the robot turns towards, and then steps into, the unknown neighbour.
+// Returns None when there is no safe frontier left to explore.
+pub fn exploration_next_move<T: ThreatCell>(robot_position: &RobotPosition, occupancy: &OccupancyMap, threat_map: &ThreatMap<T>, costs: &ExplorationCosts) -> Option<Direction> {
+    let frontier = nearest_frontier(robot_position, occupancy, threat_map, costs)?;
+
+    if frontier == robot_position.position {
+        let is_unknown = |direction| occupancy.at(robot_position.in_direction(direction)) == Occupancy::Unknown;
+        return if is_unknown(Direction::Front) {
+            Some(Direction::Front)
+        } else if is_unknown(Direction::Left) {
+            Some(Direction::Left)
+        } else {
+            Some(Direction::Right)
+        };
+    }
+
+    goal_directed_next_move(robot_position, frontier, threat_map, &occupancy.to_borders(true), &costs.navigation)
+}
+
+#[cfg(test)]
+mod exploration_tests {
+    use crate::borders::Borders;
+
+    use super::*;
+
+    fn sense(robot_position: &RobotPosition, truth: &Borders, occupancy: &mut OccupancyMap) {
+        occupancy.mark_free(robot_position.position);
+        let front = robot_position.in_direction(Direction::Front);
+        if truth.is_border(front) {
+            occupancy.mark_blocked(front);
+        } else {
+            occupancy.mark_free(front);
+        }
+    }
+
+    #[test]
+    fn test1() {
+        let mut truth = Borders::new();
+        for y in -4..=2 {
+            truth.set_border(Coordinate::new(2, y));
+        }
+        for x in -4..=0 {
+            truth.set_border(Coordinate::new(x, -2));
+        }
+
+        let mut occupancy = OccupancyMap::new();
+        let mut robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let threat_map: ThreatMap = ThreatMap::new();
+
+        for _ in 0..1000 {
+            sense(&robot_position, &truth, &mut occupancy);
+            match exploration_next_move(&robot_position, &occupancy, &threat_map, &ExplorationCosts::new()) {
+                Some(direction) => {
+                    let mut next = robot_position;
+                    next.take_step(direction);
+                    if !truth.is_border(next.position) {
+                        robot_position = next;
+                    }
+                }
+                None => break,
+            }
+            assert!(!truth.is_border(robot_position.position));
+        }
+
+        // every cell of the arena is reachable from the start, so everything got mapped
+        assert_eq!(occupancy.unknown_count(), 0);
+        for index in 0..(N * N) {
+            let coord = Coordinate::from_index(index).unwrap();
+            assert_eq!(occupancy.at(coord) == Occupancy::Blocked, truth.is_border(coord));
+        }
+    }
+
+    #[test]
+    fn test2() {
+        let mut occupancy = OccupancyMap::new();
+        for x in -4..=4 {
+            occupancy.mark_free(Coordinate::new(x, 0));
+        }
+        for x in -4..=4 {
+            occupancy.mark_blocked(Coordinate::new(x, 1));
+        }
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::East };
+
+        // an enemy north of the robot makes the frontier there unsafe
+        let mut threat_map = ThreatMap::<u8>::empty();
+        threat_map.calculate(&[Coordinate::new(0, -1), Coordinate::new(1, -1), Coordinate::new(-1, -1)]);
+        let frontier = nearest_frontier(&robot_position, &occupancy, &threat_map, &ExplorationCosts::new()).unwrap();
+        assert!(frontier.x.abs() >= 2);
+
+        let mov = exploration_next_move(&robot_position, &occupancy, &ThreatMap::new(), &ExplorationCosts::new());
+        assert_eq!(mov, Some(Direction::Left));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{coordinates::Coordinate, metric::{Connectivity, Metric}, threat_cell::ThreatCell, N};
+
+// every cell is settled once and relaxes at most eight neighbours, and the seeds are pushed once per
+// cell however many there are
+const HEAP_CAPACITY: usize = 9 * N * N;
+
+pub(crate) fn neighbours(index: usize, connectivity: Connectivity) -> impl Iterator<Item = usize> {
+    Coordinate::from_index(index).unwrap().neighbours(connectivity).filter_map(|c| c.to_index())
+}
+
+fn is_diagonal(a: usize, b: usize) -> bool {
+    a % N != b % N && a / N != b / N
+}
+
+struct MinHeap {
+    entries: [(i32, u16); HEAP_CAPACITY],
+    length: usize,
+}
+
+impl MinHeap {
+    fn new() -> Self {
+        MinHeap { entries: [(0, 0); HEAP_CAPACITY], length: 0 }
+    }
+
+    fn push(&mut self, value: i32, index: usize) {
+        // cannot happen within the bound of HEAP_CAPACITY, but an entry too many is dropped, not a panic
+        debug_assert!(self.length < HEAP_CAPACITY, "flood heap is full");
+        if self.length == HEAP_CAPACITY {
+            return;
+        }
+        let mut i = self.length;
+        self.entries[i] = (value, index as u16);
+        self.length += 1;
+        while i > 0 && self.entries[(i - 1) / 2].0 > self.entries[i].0 {
+            self.entries.swap(i, (i - 1) / 2);
+            i = (i - 1) / 2;
+        }
+    }
+
+    fn pop(&mut self) -> Option<(i32, usize)> {
+        if self.length == 0 {
+            return None;
+        }
+        let (value, index) = self.entries[0];
+        self.length -= 1;
+        self.entries[0] = self.entries[self.length];
+        let mut i = 0;
+        loop {
+            let mut smallest = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < self.length && self.entries[child].0 < self.entries[smallest].0 {
+                    smallest = child;
+                }
+            }
+            if smallest == i {
+                break;
+            }
+            self.entries.swap(i, smallest);
+            i = smallest;
+        }
+        Some((value, index as usize))
+    }
+}
+
+// Multi source shortest paths over the grid (Dijkstra), with the steps and step lengths of `metric`.
+// Seeds are path lengths as given by `Metric::path_length`. Only lowers values in `distances`, so
+// running it on an already valid map only touches the cells the seeds improve. Distances that
+// saturate the cell type are not spread any further.
+pub(crate) fn flood<T: ThreatCell, P: Fn(usize) -> bool>(distances: &mut [T; N * N], seeds: impl IntoIterator<Item = (usize, i32)>, passable: P, metric: Metric) {
+    let mut lengths = [i32::MAX; N * N];
+    for (length, &distance) in lengths.iter_mut().zip(distances.iter()) {
+        if distance != T::MAX {
+            *length = metric.path_length_of(distance.to_distance());
+        }
+    }
+    let improves = |lengths: &[i32; N * N], index: usize, length: i32| {
+        passable(index) && length < lengths[index] && T::from_distance(metric.distance_of_path(length)) != T::MAX
+    };
+
+    let mut heap = MinHeap::new();
+    let mut changed = [false; N * N];
+    // the same cell can be seeded many times, by several enemies or from outside of the map
+    for (index, length) in seeds {
+        if improves(&lengths, index, length) {
+            lengths[index] = length;
+            changed[index] = true;
+        }
+    }
+    for index in (0..(N * N)).filter(|&i| changed[i]) {
+        heap.push(lengths[index], index);
+    }
+
+    while let Some((length, index)) = heap.pop() {
+        if length > lengths[index] {
+            continue;
+        }
+        for next in neighbours(index, metric.connectivity()) {
+            let next_length = length + metric.step_length(is_diagonal(index, next));
+            if improves(&lengths, next, next_length) {
+                lengths[next] = next_length;
+                changed[next] = true;
+                heap.push(next_length, next);
+            }
+        }
+    }
+
+    for index in (0..(N * N)).filter(|&i| changed[i]) {
+        distances[index] = T::from_distance(metric.distance_of_path(lengths[index]));
+    }
+}
+
+#[cfg(test)]
+mod flood_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut distances = [i32::MAX; N * N];
+        let wall = |i: usize| Coordinate::from_index(i).unwrap().x != 0 || Coordinate::from_index(i).unwrap().y == 4;
+        flood(&mut distances, [(Coordinate::new(-1, 0).to_index().unwrap(), 0)], wall, Metric::Manhattan);
+
+        assert_eq!(distances[Coordinate::new(-1, 0).to_index().unwrap()], 0);
+        assert_eq!(distances[Coordinate::new(0, 0).to_index().unwrap()], i32::MAX);
+        assert_eq!(distances[Coordinate::new(-1, 4).to_index().unwrap()], 4);
+        assert_eq!(distances[Coordinate::new(1, 0).to_index().unwrap()], 10);
+
+        // a second seed only lowers what it can reach faster
+        flood(&mut distances, [(Coordinate::new(1, 1).to_index().unwrap(), 2)], wall, Metric::Manhattan);
+        assert_eq!(distances[Coordinate::new(1, 0).to_index().unwrap()], 3);
+        assert_eq!(distances[Coordinate::new(-1, 0).to_index().unwrap()], 0);
+    }
+
+    #[test]
+    fn test2() {
+        let open = |_: usize| true;
+        let start = Coordinate::new(-4, -4);
+        for metric in [Metric::Manhattan, Metric::Chebyshev, Metric::Octile] {
+            let mut distances = [u8::MAX; N * N];
+            flood(&mut distances, [(start.to_index().unwrap(), 0)], open, metric);
+            for (index, &distance) in distances.iter().enumerate() {
+                let coord = Coordinate::from_index(index).unwrap();
+                assert_eq!(distance as i32, metric.distance(start, coord), "{:?} at {:?}", metric, coord);
+            }
+        }
+
+        // the diagonal way around a wall
+        let mut distances = [i32::MAX; N * N];
+        let wall = |i: usize| Coordinate::from_index(i).unwrap() != Coordinate::new(0, -1);
+        flood(&mut distances, [(Coordinate::new(0, 0).to_index().unwrap(), 0)], wall, Metric::Chebyshev);
+        assert_eq!(distances[Coordinate::new(0, -2).to_index().unwrap()], 2);
+        assert_eq!(distances[Coordinate::new(0, -1).to_index().unwrap()], i32::MAX);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{borders::Borders, coordinates::Coordinate, orientation::Orientation, robot_position::RobotPosition, threat_cell::ThreatCell, threat_map::ThreatMap, transform::Transform};
+
+// The cells a robot or an enemy covers: a rectangle that reaches the given number of cells
+// in front of, behind, left and right of its reference cell, turning with its orientation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Footprint {
+    pub front: i8,
+    pub back: i8,
+    pub left: i8,
+    pub right: i8,
+}
+
+impl Footprint {
+    pub fn single() -> Self {
+        Footprint { front: 0, back: 0, left: 0, right: 0 }
+    }
+
+    // `width` cells wide and `length` cells long, with the reference cell in the front left corner
+    pub fn rectangle(width: i8, length: i8) -> Self {
+        Footprint { front: 0, back: length - 1, left: 0, right: width - 1 }
+    }
+
+    pub fn cell_count(&self) -> usize {
+        ((self.left + self.right + 1) as usize) * ((self.front + self.back + 1) as usize)
+    }
+
+    pub fn cells(&self, position: Coordinate, orientation: Orientation) -> impl Iterator<Item = Coordinate> {
+        let transform = Transform::new(position, orientation);
+        let (left, right, back) = (self.left, self.right, self.back);
+        (-self.front..=back).flat_map(move |y| (-left..=right).map(move |x| transform.apply(Coordinate::new(x, y))))
+    }
+
+    pub fn robot_cells(&self, robot_position: &RobotPosition) -> impl Iterator<Item = Coordinate> {
+        self.cells(robot_position.position, robot_position.orientation)
+    }
+
+    pub fn collides_with(&self, position: Coordinate, orientation: Orientation, borders: &Borders) -> bool {
+        self.cells(position, orientation).any(|c| borders.is_border(c))
+    }
+
+    pub fn is_inside_arena(&self, position: Coordinate, orientation: Orientation) -> bool {
+        self.cells(position, orientation).all(|c| c.to_index().is_some())
+    }
+
+    // whether two bodies with this footprint, both facing north, share a cell
+    pub fn overlaps(&self, a: Coordinate, b: Coordinate) -> bool {
+        let dx = (a.x as i32 - b.x as i32).abs();
+        let dy = (a.y as i32 - b.y as i32).abs();
+        dx <= (self.left + self.right) as i32 && dy <= (self.front + self.back) as i32
+    }
+
+    // the threat of the most threatened cell of the body
+    pub fn threat_at<T: ThreatCell>(&self, position: Coordinate, orientation: Orientation, threat_map: &ThreatMap<T>) -> i32 {
+        self.cells(position, orientation).map(|c| threat_map.distance_at(c)).min().unwrap_or(i32::MAX)
+    }
+}
+
+impl Default for Footprint {
+    fn default() -> Self {
+        Self::single()
+    }
+}
+
+#[cfg(test)]
+mod footprint_tests {
+    use crate::N;
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let footprint = Footprint::rectangle(2, 2);
+        assert_eq!(footprint.cell_count(), 4);
+        let mut cells = [Coordinate::new(0, 0); 4];
+        for (cell, c) in cells.iter_mut().zip(footprint.cells(Coordinate::new(0, 0), Orientation::North)) {
+            *cell = c;
+        }
+        assert_eq!(cells, [Coordinate::new(0, 0), Coordinate::new(1, 0), Coordinate::new(0, 1), Coordinate::new(1, 1)]);
+
+        // facing east the body reaches to the south and to the west
+        assert!(footprint.cells(Coordinate::new(0, 0), Orientation::East).any(|c| c == Coordinate::new(-1, 1)));
+        assert_eq!(Footprint::single().cells(Coordinate::new(2, 3), Orientation::West).count(), 1);
+
+        let half = (N / 2) as i8;
+        assert!(!footprint.is_inside_arena(Coordinate::new(half, 0), Orientation::North));
+        assert!(footprint.is_inside_arena(Coordinate::new(half, 0), Orientation::South));
+    }
+
+    #[test]
+    fn test2() {
+        let footprint = Footprint::rectangle(2, 2);
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(1, 1));
+        assert!(footprint.collides_with(Coordinate::new(0, 0), Orientation::North, &borders));
+        assert!(!footprint.collides_with(Coordinate::new(0, 0), Orientation::South, &borders));
+
+        assert!(footprint.overlaps(Coordinate::new(0, 0), Coordinate::new(1, -1)));
+        assert!(!footprint.overlaps(Coordinate::new(0, 0), Coordinate::new(2, 0)));
+
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate(&[Coordinate::new(3, 0)]);
+        assert_eq!(footprint.threat_at(Coordinate::new(0, 0), Orientation::North, &threat_map), 2);
+        assert_eq!(footprint.threat_at(Coordinate::new(0, 0), Orientation::South, &threat_map), 3);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, footprint::Footprint, orientation::Orientation, robot_position::RobotPosition, threat_cell::ThreatCell, threat_map::ThreatMap, N};
+
+pub(crate) const NUM_STATES: usize = N * N * 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct NavigationCosts {
+    pub step_cost: i32,
+    pub turn_cost: i32,
+    // how much a cell closer than `safe_distance` to an enemy costs per missing cell of distance
+    pub risk_aversion: i32,
+    pub safe_distance: i32,
+    // the cells the robot covers, all of them have to stay clear of borders
+    pub footprint: Footprint,
+}
+
+impl NavigationCosts {
+    pub fn new() -> Self {
+        NavigationCosts { step_cost: 1, turn_cost: 1, risk_aversion: 1, safe_distance: 3, footprint: Footprint::single() }
+    }
+
+    fn threat_penalty(&self, threat: i32) -> i32 {
+        self.risk_aversion * (self.safe_distance - threat).max(0)
+    }
+}
+
+impl Default for NavigationCosts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn state_index(robot_position: &RobotPosition) -> Option<usize> {
+    let cell = robot_position.position.to_index()?;
+    Some(cell * 4 + robot_position.orientation.integer_value() as usize)
+}
+
+pub(crate) fn state_from_index(index: usize) -> RobotPosition {
+    RobotPosition {
+        position: Coordinate::from_index(index / 4).unwrap(),
+        orientation: Orientation::from_integer((index % 4) as i32).unwrap(),
+    }
+}
+
+// A* over (cell, orientation) states, using the same actions as `RobotPosition::take_step`.
+// Returns the first move of the cheapest path towards `goal`, or None if the robot is already
+// there or the goal cannot be reached without stepping into a border.
+pub fn goal_directed_next_move<T: ThreatCell>(robot_position: &RobotPosition, goal: Coordinate, threat_map: &ThreatMap<T>, borders: &Borders, costs: &NavigationCosts) -> Option<Direction> {
+    if robot_position.position == goal || goal.to_index().is_none() || borders.is_border(goal) {
+        return None;
+    }
+    let start = state_index(robot_position)?;
+
+    let mut cost = [i32::MAX; NUM_STATES];
+    let mut first_move: [Option<Direction>; NUM_STATES] = [None; NUM_STATES];
+    let mut open = [false; NUM_STATES];
+    let mut closed = [false; NUM_STATES];
+
+    let heuristic = |position: Coordinate| position.distance(goal) * costs.step_cost;
+
+    cost[start] = 0;
+    open[start] = true;
+
+    loop {
+        let mut current = None;
+        let mut current_estimate = i32::MAX;
+        for index in 0..NUM_STATES {
+            if open[index] {
+                let estimate = cost[index] + heuristic(state_from_index(index).position);
+                if estimate < current_estimate {
+                    current = Some(index);
+                    current_estimate = estimate;
+                }
+            }
+        }
+        let current = current?;
+        open[current] = false;
+        closed[current] = true;
+
+        let state = state_from_index(current);
+        if state.position == goal {
+            return first_move[current];
+        }
+
+        for direction in [Direction::Front, Direction::Right, Direction::Back, Direction::Left] {
+            let mut next_state = state;
+            next_state.take_step(direction);
+
+            // never step or turn into a border
+            let footprint = &costs.footprint;
+            if !footprint.is_inside_arena(next_state.position, next_state.orientation) || footprint.collides_with(next_state.position, next_state.orientation, borders) {
+                continue;
+            }
+            let step_cost = if next_state.position == state.position {
+                costs.turn_cost
+            } else {
+                costs.step_cost + costs.threat_penalty(footprint.threat_at(next_state.position, next_state.orientation, threat_map))
+            };
+
+            let Some(next) = state_index(&next_state) else {
+                continue;
+            };
+            if closed[next] {
+                continue;
+            }
+
+            let next_cost = cost[current] + step_cost;
+            if next_cost < cost[next] {
+                cost[next] = next_cost;
+                first_move[next] = if current == start { Some(direction) } else { first_move[current] };
+                open[next] = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod goal_navigation_tests {
+    use super::*;
+
+    fn follow(mut robot_position: RobotPosition, goal: Coordinate, threat_map: &ThreatMap, borders: &Borders, costs: &NavigationCosts) -> (RobotPosition, i32) {
+        let mut min_threat = i32::MAX;
+        for _ in 0..50 {
+            match goal_directed_next_move(&robot_position, goal, threat_map, borders, costs) {
+                Some(direction) => robot_position.take_step(direction),
+                None => break,
+            }
+            assert!(!borders.is_border(robot_position.position));
+            min_threat = min_threat.min(threat_map.at(robot_position.position));
+        }
+        (robot_position, min_threat)
+    }
+
+    #[test]
+    fn test1() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let threat_map: ThreatMap = ThreatMap::new();
+        let borders = Borders::new();
+
+        let mov = goal_directed_next_move(&robot_position, Coordinate::new(0, -3), &threat_map, &borders, &NavigationCosts::new());
+        assert_eq!(mov, Some(Direction::Front));
+
+        let mov = goal_directed_next_move(&robot_position, Coordinate::new(0, 3), &threat_map, &borders, &NavigationCosts::new());
+        assert_eq!(mov, Some(Direction::Back));
+
+        let mov = goal_directed_next_move(&robot_position, Coordinate::new(0, 0), &threat_map, &borders, &NavigationCosts::new());
+        assert_eq!(mov, None);
+    }
+
+    #[test]
+    fn test2() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let threat_map: ThreatMap = ThreatMap::new();
+        let mut borders = Borders::new();
+        for x in -1..=1 {
+            borders.set_border(Coordinate::new(x, -1));
+        }
+
+        let (end, _) = follow(robot_position, Coordinate::new(0, -3), &threat_map, &borders, &NavigationCosts::new());
+        assert_eq!(end.position, Coordinate::new(0, -3));
+
+        // a goal enclosed by borders is unreachable
+        for x in -1..=1 {
+            borders.set_border(Coordinate::new(x, -4));
+        }
+        borders.set_border(Coordinate::new(-1, -3));
+        borders.set_border(Coordinate::new(-1, -2));
+        borders.set_border(Coordinate::new(1, -3));
+        borders.set_border(Coordinate::new(1, -2));
+        let mov = goal_directed_next_move(&robot_position, Coordinate::new(0, -3), &threat_map, &borders, &NavigationCosts::new());
+        assert_eq!(mov, None);
+    }
+
+    #[test]
+    fn test3() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 2), orientation: Orientation::North };
+        let mut threat_map: ThreatMap = ThreatMap::new();
+        threat_map.calculate(&[Coordinate::new(1, 0)]);
+        let borders = Borders::new();
+        let goal = Coordinate::new(0, -2);
+
+        let reckless = NavigationCosts { risk_aversion: 0, ..NavigationCosts::new() };
+        let (end, min_threat) = follow(robot_position, goal, &threat_map, &borders, &reckless);
+        assert_eq!(end.position, goal);
+        assert_eq!(min_threat, 1);
+
+        let cautious = NavigationCosts { risk_aversion: 10, ..NavigationCosts::new() };
+        let (end, min_threat) = follow(robot_position, goal, &threat_map, &borders, &cautious);
+        assert_eq!(end.position, goal);
+        assert!(min_threat >= 2);
+    }
+
+    #[test]
+    fn test4() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 1), orientation: Orientation::North };
+        let threat_map: ThreatMap = ThreatMap::new();
+        let mut borders = Borders::new();
+        let half = (N / 2) as i8;
+        for x in -half..=half {
+            if x != 0 {
+                borders.set_border(Coordinate::new(x, -1));
+            }
+        }
+        let goal = Coordinate::new(0, -3);
+
+        let (end, _) = follow(robot_position, goal, &threat_map, &borders, &NavigationCosts::new());
+        assert_eq!(end.position, goal);
+
+        // a 2x2 robot does not fit through the gap
+        let wide = NavigationCosts { footprint: Footprint::rectangle(2, 2), ..NavigationCosts::new() };
+        assert_eq!(goal_directed_next_move(&robot_position, goal, &threat_map, &borders, &wide), None);
+
+        borders.clear_border(Coordinate::new(1, -1));
+        let mut robot_position = robot_position;
+        for _ in 0..20 {
+            match goal_directed_next_move(&robot_position, goal, &threat_map, &borders, &wide) {
+                Some(direction) => robot_position.take_step(direction),
+                None => break,
+            }
+            assert!(!wide.footprint.collides_with(robot_position.position, robot_position.orientation, &borders));
+        }
+        assert_eq!(robot_position.position, goal);
+    }
+}
@@ -1,39 +1,323 @@
-use crate::{borders::Borders, direction::Direction, enemy_position_prediction::EnemyPositionPrediction, robot_position::RobotPosition};
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, enemy_position_prediction::EnemyPositionPrediction, evaluation::{Evaluation, Scene}, footprint::Footprint, metric::Metric, position_history::PositionHistory, rng::Rng, robot_position::RobotPosition, threat_cell::ThreatCell};
+
+// the order moves are judged in, `greedy_next_move` takes the last of the safest
+const MOVE_ORDER: [Direction; 4] = [Direction::Right, Direction::Left, Direction::Front, Direction::Back];
 
 pub fn greedy_next_move(robot_position: &RobotPosition, enemy_position_prediction: &EnemyPositionPrediction, borders: &Borders) -> Option<Direction> {
-    let mut best_direction = None;
-    let mut best_direction_survival_chance = enemy_position_prediction.min_distance_from(robot_position.position);
+    greedy_next_move_with_footprint(robot_position, enemy_position_prediction, borders, &Footprint::single())
+}
+
+// Like `greedy_next_move` for a robot covering more than one cell: a move is only taken when the
+// whole body stays clear of borders, and it is judged by the body cell closest to an enemy.
+pub fn greedy_next_move_with_footprint(robot_position: &RobotPosition, enemy_position_prediction: &EnemyPositionPrediction, borders: &Borders, footprint: &Footprint) -> Option<Direction> {
+    greedy_next_move_with_evaluation(robot_position, &Scene::new(enemy_position_prediction, borders), footprint, &Evaluation::new())
+}
+
+// Like `greedy_next_move_with_footprint`, maximising `evaluation` instead of the distance to the
+// closest enemy.
+pub fn greedy_next_move_with_evaluation<T: ThreatCell>(robot_position: &RobotPosition, scene: &Scene<T>, footprint: &Footprint, evaluation: &Evaluation) -> Option<Direction> {
+    let (moves, count, _) = safest_moves(robot_position, scene, footprint, evaluation);
+    moves[..count].last().copied()
+}
+
+// Like `greedy_next_move_with_evaluation`, breaking ties the way `TieBreak::PreferFront` does, for
+// the planners that fall back on greedy and should not back off when nothing threatens the robot.
+pub fn greedy_next_move_preferring_front<T: ThreatCell>(robot_position: &RobotPosition, scene: &Scene<T>, footprint: &Footprint, evaluation: &Evaluation) -> Option<Direction> {
+    let (moves, count, _) = safest_moves(robot_position, scene, footprint, evaluation);
+    prefer_front(&moves[..count])
+}
+
+// Front, then Right, Left and Back
+fn prefer_front(ties: &[Direction]) -> Option<Direction> {
+    let preference = [Direction::Front, Direction::Right, Direction::Left, Direction::Back];
+    ties.iter().copied().min_by_key(|d| preference.iter().position(|p| p == d))
+}
+
+// The moves that keep the robot farthest from the enemies, in `MOVE_ORDER`, and whether they are
+// only as safe as staying where it is. Empty when every move brings the robot closer.
+fn safest_moves<T: ThreatCell>(robot_position: &RobotPosition, scene: &Scene<T>, footprint: &Footprint, evaluation: &Evaluation) -> ([Direction; 4], usize, bool) {
+    let mut moves = [Direction::Front; 4];
+    let mut count = 0;
+    let staying_survival_chance = evaluation.evaluate(robot_position, footprint, scene);
+    let mut best_direction_survival_chance = staying_survival_chance;
 
-    for direction in [Direction::Right, Direction::Left, Direction::Front, Direction::Back] {
-        let pos = robot_position.in_direction(direction);
-        // do not go to a border
-        if borders.is_border(pos) {
+    for direction in MOVE_ORDER {
+        let pos = RobotPosition { position: robot_position.in_direction(direction), orientation: robot_position.orientation };
+        // do not go to a border or hang off the edge of the arena
+        if !footprint.is_inside_arena(pos.position, pos.orientation) || footprint.collides_with(pos.position, pos.orientation, scene.borders) {
             continue;
         }
-        let survival_chance = enemy_position_prediction.min_distance_from(pos);
+        let survival_chance = evaluation.evaluate(&pos, footprint, scene);
+        if survival_chance > best_direction_survival_chance {
+            count = 0;
+        }
         if survival_chance >= best_direction_survival_chance {
-            best_direction = Some(direction);
+            moves[count] = direction;
+            count += 1;
             best_direction_survival_chance = survival_chance;
         }
     }
-    
-    best_direction
+
+    (moves, count, best_direction_survival_chance == staying_survival_chance)
+}
+
+// How `GreedyPlanner` picks among equally safe moves.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TieBreak {
+    // the last in the order Right, Left, Front, Back, like `greedy_next_move`
+    #[default]
+    MoveOrder,
+    // Front, then Right, Left and Back
+    PreferFront,
+    // the move that repeats the robot's last step, Front if it has not moved yet, and like
+    // PreferFront when that move is not among the ties
+    KeepHeading,
+    // the move ending closest to the centre of the map
+    TowardCentre,
+    // a seeded random one
+    Random,
+}
+
+// `greedy_next_move` with a choice of tie breaking. It remembers where the robot has been and
+// among equally safe moves avoids the cells it visited most often, so that it does not go back and
+// forth between two of them. When all of those lead back and staying is as safe, it stays.
+#[derive(Debug, Clone)]
+pub struct GreedyPlanner {
+    tie_break: TieBreak,
+    footprint: Footprint,
+    evaluation: Evaluation,
+    avoid_oscillation: bool,
+    history: PositionHistory,
+    rng: Rng,
+}
+
+impl GreedyPlanner {
+    pub fn new() -> Self {
+        GreedyPlanner { tie_break: TieBreak::MoveOrder, footprint: Footprint::single(), evaluation: Evaluation::new(), avoid_oscillation: true, history: PositionHistory::new(), rng: Rng::new(1) }
+    }
+
+    pub fn use_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break
+    }
+
+    pub fn use_footprint(&mut self, footprint: Footprint) {
+        self.footprint = footprint
+    }
+
+    pub fn use_evaluation(&mut self, evaluation: Evaluation) {
+        self.evaluation = evaluation
+    }
+
+    pub fn use_oscillation_avoidance(&mut self, avoid_oscillation: bool) {
+        self.avoid_oscillation = avoid_oscillation
+    }
+
+    pub fn use_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed)
+    }
+
+    pub fn history(&self) -> &PositionHistory {
+        &self.history
+    }
+
+    // Picks the next move and remembers the current position of the robot.
+    pub fn next_move(&mut self, robot_position: &RobotPosition, enemy_position_prediction: &EnemyPositionPrediction, borders: &Borders) -> Option<Direction> {
+        self.next_move_in(robot_position, &Scene::new(enemy_position_prediction, borders))
+    }
+
+    // `next_move` for evaluations that need more than the enemies and the borders, like a threat map
+    pub fn next_move_in<T: ThreatCell>(&mut self, robot_position: &RobotPosition, scene: &Scene<T>) -> Option<Direction> {
+        let (moves, count, as_safe_as_staying) = safest_moves(robot_position, scene, &self.footprint, &self.evaluation);
+        let mut ties = [Direction::Front; 4];
+        let mut tie_count = 0;
+        let fewest_visits = moves[..count].iter().map(|&d| self.visits(robot_position, d)).min().unwrap_or(0);
+        for &direction in &moves[..count] {
+            if self.visits(robot_position, direction) == fewest_visits {
+                ties[tie_count] = direction;
+                tie_count += 1;
+            }
+        }
+        let ties = &ties[..tie_count];
+        if fewest_visits > 0 && as_safe_as_staying {
+            self.history.push(robot_position.position);
+            return None;
+        }
+
+        let choice = match self.tie_break {
+            TieBreak::MoveOrder => ties.last().copied(),
+            TieBreak::PreferFront => prefer_front(ties),
+            TieBreak::KeepHeading => {
+                let last_step = self.history.latest().map(|p| robot_position.position - p).filter(|&step| step != Coordinate::new(0, 0));
+                let heading = last_step.map(|step| robot_position.position + step).unwrap_or(robot_position.in_direction(Direction::Front));
+                ties.iter().copied().find(|&d| robot_position.in_direction(d) == heading).or_else(|| prefer_front(ties))
+            }
+            TieBreak::TowardCentre => ties.iter().copied().min_by_key(|&d| Metric::Manhattan.distance(robot_position.in_direction(d), Coordinate::new(0, 0))),
+            TieBreak::Random if ties.is_empty() => None,
+            TieBreak::Random => Some(ties[self.rng.below(ties.len() as u32) as usize]),
+        };
+
+        self.history.push(robot_position.position);
+        choice
+    }
+
+    fn visits(&self, robot_position: &RobotPosition, direction: Direction) -> usize {
+        if self.avoid_oscillation { self.history.visits(robot_position.in_direction(direction)) } else { 0 }
+    }
+}
+
+impl Default for GreedyPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::num::NonZero;
+
     use crate::coordinates::Coordinate;
+    use crate::enemy_position::{EnemyPosition, EnemyPositions};
+    use crate::evaluation::Term;
     use crate::orientation::Orientation;
+    use crate::N;
 
     use super::*;
 
     #[test]
     fn test1() {
-        // let mut enemies = EnemyPositionPrediction::new();
-        // let bot_coords = [Coordinate::new(-1, -1)];
-        // threat_map.calculate(&bot_coords);
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-1, -1)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+
+        let mov = greedy_next_move(&RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::West }, &prediction, &Borders::new());
+        assert_eq!(mov, Some(Direction::Back));
+    }
+
+    #[test]
+    fn test2() {
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-3, 0)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(1, 1));
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::East };
+
+        // a single cell robot runs straight away, a 2x2 robot would scrape the border with its right side
+        let mov = greedy_next_move(&robot_position, &prediction, &borders);
+        assert_eq!(mov, Some(Direction::Front));
+        let mov = greedy_next_move_with_footprint(&robot_position, &prediction, &borders, &Footprint::rectangle(2, 2));
+        assert_eq!(mov, Some(Direction::Right));
+
+        // at the edge of the arena running on is no way out, not even for the part of a wider body
+        let half = (N / 2) as i8;
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(half - 3, 0)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+        let robot_position = RobotPosition { position: Coordinate::new(half, 0), orientation: Orientation::East };
+        assert_ne!(greedy_next_move(&robot_position, &prediction, &Borders::new()), Some(Direction::Front));
+        let robot_position = RobotPosition { position: Coordinate::new(half - 1, 0), orientation: Orientation::North };
+        let mov = greedy_next_move_with_footprint(&robot_position, &prediction, &Borders::new(), &Footprint::rectangle(2, 2));
+        assert!(mov.is_some());
+        assert_ne!(mov, Some(Direction::Right));
+    }
+
+    #[test]
+    fn test3() {
+        let prediction = EnemyPositionPrediction::new(&EnemyPositions::new(), &EnemyPositions::new(), Borders::new());
+        let robot_position = RobotPosition { position: Coordinate::new(1, 0), orientation: Orientation::North };
+
+        // with no enemy around every move is as safe as any other
+        let next_move = |tie_break| {
+            let mut planner = GreedyPlanner::new();
+            planner.use_tie_break(tie_break);
+            planner.next_move(&robot_position, &prediction, &Borders::new())
+        };
+        assert_eq!(next_move(TieBreak::MoveOrder), Some(Direction::Back));
+        assert_eq!(next_move(TieBreak::PreferFront), Some(Direction::Front));
+        assert_eq!(next_move(TieBreak::KeepHeading), Some(Direction::Front));
+        assert_eq!(next_move(TieBreak::TowardCentre), Some(Direction::Left));
+        let borders = Borders::new();
+        assert_eq!(greedy_next_move_preferring_front(&robot_position, &Scene::new(&prediction, &borders), &Footprint::single(), &Evaluation::new()), Some(Direction::Front));
+
+        // keeping the heading repeats the last step, even a sideways one
+        let mut planner = GreedyPlanner::new();
+        planner.use_tie_break(TieBreak::KeepHeading);
+        planner.next_move(&RobotPosition { position: Coordinate::new(0, 0), ..robot_position }, &prediction, &Borders::new());
+        assert_eq!(planner.next_move(&robot_position, &prediction, &Borders::new()), Some(Direction::Right));
+
+        // and goes ahead rather than back when that step is blocked
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(2, 0));
+        let mut planner = GreedyPlanner::new();
+        planner.use_tie_break(TieBreak::KeepHeading);
+        planner.next_move(&RobotPosition { position: Coordinate::new(0, 0), ..robot_position }, &prediction, &borders);
+        assert_eq!(planner.next_move(&robot_position, &prediction, &borders), Some(Direction::Front));
+
+        // the same seed picks the same moves
+        let mut a = GreedyPlanner::new();
+        let mut b = GreedyPlanner::new();
+        for planner in [&mut a, &mut b] {
+            planner.use_tie_break(TieBreak::Random);
+            planner.use_seed(5);
+            planner.use_oscillation_avoidance(false);
+        }
+        let mut seen = [false; 4];
+        for _ in 0..20 {
+            let mov = a.next_move(&robot_position, &prediction, &Borders::new()).unwrap();
+            assert_eq!(Some(mov), b.next_move(&robot_position, &prediction, &Borders::new()));
+            seen[mov as usize] = true;
+        }
+        assert_eq!(seen, [true; 4]);
+    }
+
+    #[test]
+    fn test4() {
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-3, 0)));
+        enemies.push(EnemyPosition::new(NonZero::new(2).unwrap(), Coordinate::new(4, 0)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+        let mut borders = Borders::new();
+        for x in -1..=2 {
+            borders.set_border(Coordinate::new(x, -1));
+            borders.set_border(Coordinate::new(x, 1));
+        }
+
+        // in a corridor between two enemies (0, 0) and (1, 0) are equally safe, and from each of
+        // them the only move that is not worse leads to the other one
+        let run = |planner: &mut GreedyPlanner| {
+            let mut robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+            for _ in 0..8 {
+                if let Some(mov) = planner.next_move(&robot_position, &prediction, &borders) {
+                    robot_position.position = robot_position.in_direction(mov);
+                }
+            }
+            planner.history().is_oscillating()
+        };
+        let mut plain = GreedyPlanner::new();
+        plain.use_oscillation_avoidance(false);
+        assert!(run(&mut plain));
+        let mut planner = GreedyPlanner::new();
+        assert!(!run(&mut planner));
+        assert_eq!(planner.history().latest(), Some(Coordinate::new(1, 0)));
+    }
+
+    #[test]
+    fn test5() {
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-4, -4)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+        let borders = Borders::new();
+        let robot_position = RobotPosition { position: Coordinate::new(3, 3), orientation: Orientation::North };
+
+        // running from the enemy leads into the corner, unless staying off the walls counts too
+        assert_eq!(greedy_next_move(&robot_position, &prediction, &borders), Some(Direction::Back));
+        let mut evaluation = Evaluation::new();
+        evaluation.use_weight(Term::WallDistance, 3);
+        let scene = Scene::new(&prediction, &borders);
+        assert_eq!(greedy_next_move_with_evaluation(&robot_position, &scene, &Footprint::single(), &evaluation), None);
 
-        // let mov = greedy_next_move(&threat_map, &RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::West });
-        // assert_eq!(mov, Some(Direction::Back));
+        let mut planner = GreedyPlanner::new();
+        planner.use_evaluation(evaluation);
+        assert_eq!(planner.next_move(&robot_position, &prediction, &borders), None);
     }
 }
@@ -0,0 +1,269 @@
+use core::num::NonZero;
+
+use crate::{borders::Borders, coordinates::Coordinate, enemy_position::{EnemyPosition, EnemyPositions}, flood::{flood, neighbours}, metric::Metric, threat_cell::ThreatCell, threat_map::{flood_seeds, ThreatMap}, MAX_NUM_ENEMIES, N};
+
+// A threat map that follows enemies and borders as they change instead of recalculating every cell.
+// It always equals `ThreatMap::calculate_with_borders` over the current enemies and borders.
+pub struct IncrementalThreatMap<T: ThreatCell = i32> {
+    threat_map: ThreatMap<T>,
+    borders: Borders,
+    enemies: EnemyPositions,
+}
+
+impl<T: ThreatCell> IncrementalThreatMap<T> {
+    pub fn new(borders: Borders) -> Self {
+        let mut map = IncrementalThreatMap { threat_map: ThreatMap::empty(), borders, enemies: EnemyPositions::new() };
+        map.recalculate();
+        map
+    }
+
+    pub fn from_enemies(enemies: &EnemyPositions, borders: Borders) -> Self {
+        let mut map = IncrementalThreatMap { threat_map: ThreatMap::empty(), borders, enemies: enemies.clone() };
+        map.recalculate();
+        map
+    }
+
+    pub fn threat_map(&self) -> &ThreatMap<T> {
+        &self.threat_map
+    }
+
+    pub fn borders(&self) -> &Borders {
+        &self.borders
+    }
+
+    pub fn enemies(&self) -> &EnemyPositions {
+        &self.enemies
+    }
+
+    // Switches the metric of the threat map. The map is only updated incrementally for metrics where
+    // every step adds one, otherwise every change recalculates it.
+    pub fn use_metric(&mut self, metric: Metric) {
+        self.threat_map.use_metric(metric);
+        self.recalculate();
+    }
+
+    pub fn recalculate(&mut self) {
+        let mut positions = [Coordinate::new(0, 0); MAX_NUM_ENEMIES];
+        for (position, enemy) in positions.iter_mut().zip(self.enemies.iter()) {
+            *position = enemy.position;
+        }
+        self.threat_map.calculate_with_borders(&positions[..self.enemies.len()], &self.borders);
+    }
+
+    fn passable(borders: &Borders, index: usize) -> bool {
+        !borders.is_border(Coordinate::from_index(index).unwrap())
+    }
+
+    // A new enemy can only lower distances, so flooding from it is enough. An enemy with the id of
+    // one already in the map replaces it.
+    pub fn add_enemy(&mut self, enemy: EnemyPosition) {
+        self.remove_enemy(enemy.id);
+        let length = self.enemies.len();
+        self.enemies.push(enemy);
+        if self.enemies.len() == length {
+            return;
+        }
+        let metric = self.threat_map.metric();
+        if !metric.has_unit_steps() {
+            self.recalculate();
+            return;
+        }
+        let borders = &self.borders;
+        flood(self.threat_map.cells_mut(), flood_seeds(enemy.position, metric), |i| Self::passable(borders, i), metric);
+    }
+
+    pub fn remove_enemy(&mut self, id: NonZero<u64>) -> bool {
+        let Some(enemy) = self.enemies.remove(id) else {
+            return false;
+        };
+        let metric = self.threat_map.metric();
+        if !metric.has_unit_steps() {
+            self.recalculate();
+            return true;
+        }
+        // the cells that got their value from the removed enemy are the ones reachable from it
+        // while the value keeps growing by exactly one step
+        let mut affected = [false; N * N];
+        let mut any_affected = false;
+        for (index, value) in flood_seeds(enemy.position, metric) {
+            if Self::passable(&self.borders, index) && self.threat_map.cells_mut()[index] == T::from_distance(value) {
+                affected[index] = true;
+                any_affected = true;
+                self.spread_affected(&mut affected, index);
+            }
+        }
+        // otherwise the enemy was not the closest one to any cell
+        if any_affected {
+            self.repair(&affected);
+        }
+        true
+    }
+
+    pub fn move_enemy(&mut self, id: NonZero<u64>, position: Coordinate) -> bool {
+        if !self.remove_enemy(id) {
+            return false;
+        }
+        self.add_enemy(EnemyPosition::new(id, position));
+        true
+    }
+
+    pub fn add_border(&mut self, coord: Coordinate) {
+        let Some(index) = coord.to_index() else {
+            return;
+        };
+        if self.borders.is_border(coord) {
+            return;
+        }
+        if !self.threat_map.metric().has_unit_steps() {
+            self.borders.set_border(coord);
+            self.recalculate();
+            return;
+        }
+
+        // every cell whose shortest path might pass through the new border has to be repaired
+        let mut affected = [false; N * N];
+        affected[index] = true;
+        self.spread_affected(&mut affected, index);
+        self.borders.set_border(coord);
+        self.repair(&affected);
+        self.threat_map.mask_border(coord);
+    }
+
+    fn spread_affected(&mut self, affected: &mut [bool; N * N], start: usize) {
+        let connectivity = self.threat_map.metric().connectivity();
+        let map = self.threat_map.cells_mut();
+        let mut queue = [0usize; N * N];
+        let (mut head, mut tail) = (0, 1);
+        queue[0] = start;
+
+        while head < tail {
+            let index = queue[head];
+            head += 1;
+            if map[index] == T::MAX {
+                continue;
+            }
+            for next in neighbours(index, connectivity) {
+                if !affected[next] && Self::passable(&self.borders, next) && map[next] == T::from_distance(map[index].to_distance() + 1) {
+                    affected[next] = true;
+                    queue[tail] = next;
+                    tail += 1;
+                }
+            }
+        }
+    }
+
+    // Forgets the affected cells and floods them again from their unaffected neighbours and
+    // from the enemies standing inside of them.
+    fn repair(&mut self, affected: &[bool; N * N]) {
+        let metric = self.threat_map.metric();
+        let borders = &self.borders;
+        let map = self.threat_map.cells_mut();
+        for (value, _) in map.iter_mut().zip(affected).filter(|(_, &a)| a) {
+            *value = T::MAX;
+        }
+
+        let mut seeds = [i32::MAX; N * N];
+        for index in (0..(N * N)).filter(|&i| affected[i] && Self::passable(borders, i)) {
+            for next in neighbours(index, metric.connectivity()) {
+                if !affected[next] && Self::passable(borders, next) && map[next] != T::MAX {
+                    seeds[index] = seeds[index].min(map[next].to_distance() + 1);
+                }
+            }
+        }
+        for (index, value) in self.enemies.iter().flat_map(|e| flood_seeds(e.position, metric)) {
+            if affected[index] {
+                seeds[index] = seeds[index].min(value);
+            }
+        }
+
+        flood(map, seeds.iter().enumerate().filter(|(_, &v)| v != i32::MAX).map(|(i, &v)| (i, v)), |i| Self::passable(borders, i), metric);
+    }
+}
+
+#[cfg(test)]
+mod incremental_threat_map_tests {
+    use crate::rng::Rng;
+
+    use super::*;
+
+    fn assert_matches_full_calculation<T: ThreatCell>(map: &IncrementalThreatMap<T>) {
+        let mut positions = [Coordinate::new(0, 0); MAX_NUM_ENEMIES];
+        for (position, enemy) in positions.iter_mut().zip(map.enemies()) {
+            *position = enemy.position;
+        }
+        let mut expected = ThreatMap::<T>::empty();
+        expected.use_metric(map.threat_map().metric());
+        expected.calculate_with_borders(&positions[..map.enemies().len()], map.borders());
+        for index in 0..(N * N) {
+            let coord = Coordinate::from_index(index).unwrap();
+            assert_eq!(map.threat_map().at(coord), expected.at(coord), "at {:?}", coord);
+        }
+    }
+
+    fn random_coordinate(rng: &mut Rng, bound: i8) -> Coordinate {
+        let size = 2 * bound as u32 + 1;
+        Coordinate::new(rng.below(size) as i8 - bound, rng.below(size) as i8 - bound)
+    }
+
+    #[test]
+    fn test1() {
+        // a wall with a gap at its north end, which the enemy has to go around
+        let half = (N / 2) as i8;
+        let mut borders = Borders::new();
+        for y in -half + 1..=half {
+            borders.set_border(Coordinate::new(1, y));
+        }
+        let mut map: IncrementalThreatMap = IncrementalThreatMap::new(borders);
+        let id = NonZero::new(1).unwrap();
+
+        map.add_enemy(EnemyPosition::new(id, Coordinate::new(3, 0)));
+        assert_eq!(map.threat_map().at(Coordinate::new(0, 0)), 2 * half as i32 + 3);
+        assert_matches_full_calculation(&map);
+
+        map.move_enemy(id, Coordinate::new(-3, 0));
+        assert_eq!(map.threat_map().at(Coordinate::new(0, 0)), 3);
+        assert_matches_full_calculation(&map);
+
+        // adding it again moves it
+        map.add_enemy(EnemyPosition::new(id, Coordinate::new(-2, 0)));
+        assert_eq!(map.enemies().len(), 1);
+        assert_eq!(map.threat_map().at(Coordinate::new(0, 0)), 2);
+        assert_matches_full_calculation(&map);
+        map.move_enemy(id, Coordinate::new(-3, 0));
+
+        map.add_border(Coordinate::new(1, -half));
+        assert_eq!(map.threat_map().at(Coordinate::new(3, 0)), i32::MAX);
+        assert_matches_full_calculation(&map);
+
+        assert!(map.remove_enemy(id));
+        assert!(!map.remove_enemy(id));
+        assert_eq!(map.threat_map().at(Coordinate::new(-3, 0)), i32::MAX);
+        assert_matches_full_calculation(&map);
+    }
+
+    #[test]
+    fn test2() {
+        let mut rng = Rng::new(33);
+        for round in 0..50 {
+            let mut borders = Borders::new();
+            for _ in 0..rng.below(15) {
+                borders.set_border(random_coordinate(&mut rng, 4));
+            }
+            let mut map: IncrementalThreatMap<u8> = IncrementalThreatMap::new(borders);
+            map.use_metric([Metric::Manhattan, Metric::Chebyshev, Metric::Octile][round % 3]);
+
+            for _ in 0..40 {
+                let id = NonZero::new(rng.below(6) as u64 + 1).unwrap();
+                // enemies are sometimes seen outside of the map
+                let position = random_coordinate(&mut rng, 6);
+                match rng.below(4) {
+                    0 => map.add_enemy(EnemyPosition::new(id, position)),
+                    1 => { map.remove_enemy(id); }
+                    2 => { map.move_enemy(id, position); }
+                    _ => map.add_border(random_coordinate(&mut rng, 4)),
+                }
+                assert_matches_full_calculation(&map);
+            }
+        }
+    }
+}
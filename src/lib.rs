@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 pub mod coordinates;
 pub mod threat_map;
@@ -9,6 +9,40 @@ pub mod robot_position;
 pub mod enemy_position;
 pub mod enemy_position_prediction;
 pub mod borders;
+pub mod goal_navigation;
+pub mod occupancy;
+pub mod exploration;
+pub mod border_learning;
+pub mod enemy_history;
+pub mod behaviour_classification;
+pub mod rng;
+pub mod particle_filter;
+pub mod line;
+pub mod visibility;
+pub mod incremental_threat_map;
+pub mod threat_cell;
+pub mod transform;
+pub mod metric;
+pub mod footprint;
+pub mod simulator;
+pub mod mcts;
+pub mod budget;
+pub mod lookahead_next_move;
+pub mod position_history;
+pub mod evaluation;
+#[cfg(feature = "std")]
+pub mod tuning;
+pub mod team;
+pub mod pursuit;
+mod flood;
 
+#[cfg(not(feature = "large-grid"))]
 pub const N: usize = 9;
+// a bigger arena for benchmarking
+#[cfg(feature = "large-grid")]
+pub const N: usize = 15;
 pub const MAX_NUM_ENEMIES: usize = 10;
+pub const HISTORY_LENGTH: usize = 8;
+pub const NUM_PARTICLES: usize = 64;
+pub const MCTS_NODES: usize = 2048;
+pub const MAX_TEAM_SIZE: usize = 4;
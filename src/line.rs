@@ -0,0 +1,76 @@
+use crate::coordinates::Coordinate;
+
+// Bresenham line between two cells, both ends included.
+#[derive(Debug, Clone)]
+pub struct Line {
+    current: Coordinate,
+    end: Coordinate,
+    dx: i32,
+    dy: i32,
+    step_x: i8,
+    step_y: i8,
+    error: i32,
+    done: bool,
+}
+
+impl Line {
+    pub fn new(from: Coordinate, to: Coordinate) -> Self {
+        let dx = (to.x as i32 - from.x as i32).abs();
+        let dy = -(to.y as i32 - from.y as i32).abs();
+        Line {
+            current: from,
+            end: to,
+            dx,
+            dy,
+            step_x: if from.x < to.x { 1 } else { -1 },
+            step_y: if from.y < to.y { 1 } else { -1 },
+            error: dx + dy,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Line {
+    type Item = Coordinate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.current;
+        if self.current == self.end {
+            self.done = true;
+            return Some(result);
+        }
+
+        let doubled_error = 2 * self.error;
+        if doubled_error >= self.dy {
+            self.error += self.dy;
+            self.current.x += self.step_x;
+        }
+        if doubled_error <= self.dx {
+            self.error += self.dx;
+            self.current.y += self.step_y;
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod line_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut line = Line::new(Coordinate::new(0, 0), Coordinate::new(3, 1));
+        assert_eq!(line.next(), Some(Coordinate::new(0, 0)));
+        assert_eq!(line.next(), Some(Coordinate::new(1, 0)));
+        assert_eq!(line.next(), Some(Coordinate::new(2, 1)));
+        assert_eq!(line.next(), Some(Coordinate::new(3, 1)));
+        assert_eq!(line.next(), None);
+
+        assert_eq!(Line::new(Coordinate::new(2, 2), Coordinate::new(2, 2)).count(), 1);
+        assert_eq!(Line::new(Coordinate::new(-2, 2), Coordinate::new(1, -1)).count(), 4);
+        assert_eq!(Line::new(Coordinate::new(0, -4), Coordinate::new(0, 4)).last(), Some(Coordinate::new(0, 4)));
+    }
+}
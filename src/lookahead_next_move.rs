@@ -0,0 +1,205 @@
+use crate::{borders::Borders, budget::Budget, direction::Direction, enemy_position_prediction::EnemyPositionPrediction, evaluation::{Evaluation, Scene}, footprint::Footprint, greedy_next_move::greedy_next_move_preferring_front, robot_position::{RobotPosition, SIDESTEPS}, threat_cell::ThreatCell};
+
+// the deepest search of the iterative deepening, in ticks
+pub const MAX_LOOKAHEAD: usize = 8;
+// enemies at least this far away count as harmless, every tick scores its distance up to this
+const SAFE_DISTANCE: i32 = 4;
+
+struct Search<'a, B: Budget, T: ThreatCell> {
+    // the enemies after 0, 1, 2, ... ticks
+    predictions: &'a [EnemyPositionPrediction],
+    borders: &'a Borders,
+    budget: &'a mut B,
+    // the scene with the enemies one tick on, where the evaluation tells the first moves apart
+    scene: &'a Scene<'a, T>,
+    evaluation: &'a Evaluation,
+}
+
+impl<B: Budget, T: ThreatCell> Search<'_, B, T> {
+    // The robot is caught when it steps onto an enemy or an enemy steps onto it, a score of 0.
+    fn step_score(&self, robot_position: &RobotPosition, tick: usize) -> i32 {
+        let before = self.predictions[tick - 1].min_distance_from(robot_position.position);
+        let after = self.predictions[tick].min_distance_from(robot_position.position);
+        before.min(after).min(SAFE_DISTANCE)
+    }
+
+    // The best total score of the next `depth` ticks after `tick`, or None when the budget ran out.
+    // Moves that cannot score more than `bound` are not searched.
+    fn best_score(&mut self, robot_position: &RobotPosition, tick: usize, depth: usize, bound: i32) -> Option<i32> {
+        if depth == 0 {
+            return Some(0);
+        }
+        let mut best = 0;
+        for action in SIDESTEPS {
+            let Some(next) = self.next_position(robot_position, action) else {
+                continue;
+            };
+            if !self.budget.spend() {
+                return None;
+            }
+            let score = self.step_score(&next, tick + 1);
+            if score == 0 || score + (depth as i32 - 1) * SAFE_DISTANCE <= best.max(bound) {
+                continue;
+            }
+            let rest = self.best_score(&next, tick + 1, depth - 1, best.max(bound) - score)?;
+            best = best.max(score + rest);
+        }
+        Some(best)
+    }
+
+    // where `action` takes the robot, None if that is into a border or out of the arena
+    fn next_position(&self, robot_position: &RobotPosition, action: Option<Direction>) -> Option<RobotPosition> {
+        if !robot_position.can_sidestep(action, self.borders) {
+            return None;
+        }
+        let mut next = *robot_position;
+        if let Some(direction) = action {
+            next.sidestep(direction);
+        }
+        Some(next)
+    }
+
+    // The best move when looking `depth` ticks ahead, None to stay, trying `first` before the
+    // others. Moves that score the same are told apart by the evaluation of where they lead, after
+    // the enemies made their first move. None when the budget ran out.
+    fn best_move(&mut self, robot_position: &RobotPosition, depth: usize, first: Option<Direction>) -> Option<Option<Direction>> {
+        let mut best = None;
+        let mut best_score = (-1, i64::MIN);
+        let order = core::iter::once(first).chain(SIDESTEPS.into_iter().filter(|&a| a != first));
+        for action in order {
+            let Some(next) = self.next_position(robot_position, action) else {
+                continue;
+            };
+            if !self.budget.spend() {
+                return None;
+            }
+            let score = self.step_score(&next, 1);
+            // a move that only ties the best still has to be searched to know it ties
+            let rest = if score == 0 { 0 } else { self.best_score(&next, 1, depth - 1, best_score.0 - score - 1)? };
+            let total = (score + rest, self.evaluation.evaluate(&next, &Footprint::single(), self.scene));
+            if total > best_score {
+                best = action;
+                best_score = total;
+            }
+        }
+        Some(best)
+    }
+}
+
+// Searches the robot's moves one tick deeper at a time until `budget` runs out and returns the
+// best move of the deepest search that finished, None to stay. The robot sidesteps like in
+// `greedy_next_move`. Without budget for even one tick of lookahead
+// this is the move of `greedy_next_move_preferring_front`.
+pub fn lookahead_next_move<B: Budget>(robot_position: &RobotPosition, enemy_position_prediction: &EnemyPositionPrediction, borders: &Borders, budget: &mut B) -> Option<Direction> {
+    lookahead_next_move_with_evaluation(robot_position, &Scene::new(enemy_position_prediction, borders), &Evaluation::new(), budget)
+}
+
+// Like `lookahead_next_move`, breaking ties and falling back to greedy with `evaluation`. The
+// search itself only counts the distance to the enemies, the evaluation only decides between first
+// moves that tie on it.
+pub fn lookahead_next_move_with_evaluation<B: Budget, T: ThreatCell>(robot_position: &RobotPosition, scene: &Scene<T>, evaluation: &Evaluation, budget: &mut B) -> Option<Direction> {
+    let mut best = greedy_next_move_preferring_front(robot_position, scene, &Footprint::single(), evaluation);
+
+    let mut predictions: [EnemyPositionPrediction; MAX_LOOKAHEAD + 1] = core::array::from_fn(|_| scene.prediction.clone());
+    for tick in 1..=MAX_LOOKAHEAD {
+        predictions[tick] = predictions[tick - 1].clone();
+        predictions[tick].move_enemies();
+    }
+
+    let after_first_tick = Scene { prediction: &predictions[1], ..*scene };
+    let mut search = Search { predictions: &predictions, borders: scene.borders, budget, scene: &after_first_tick, evaluation };
+    for depth in 1..=MAX_LOOKAHEAD {
+        // the best move so far goes first, so that a deeper search cuts more
+        match search.best_move(robot_position, depth, best) {
+            Some(action) => best = action,
+            None => break,
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod lookahead_next_move_tests {
+    use core::num::NonZero;
+
+    use crate::budget::NodeBudget;
+    use crate::coordinates::Coordinate;
+    use crate::enemy_position::{EnemyPosition, EnemyPositions};
+    use crate::orientation::Orientation;
+    use crate::greedy_next_move::greedy_next_move;
+    use crate::robot_position::MoveModel;
+    use crate::simulator::{SimulatedBehaviour, Simulator};
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-1, -1)));
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), Borders::new());
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::West };
+
+        // without any budget the greedy move is returned
+        let greedy = greedy_next_move_preferring_front(&robot_position, &Scene::new(&prediction, &Borders::new()), &Footprint::single(), &Evaluation::new());
+        assert_eq!(lookahead_next_move(&robot_position, &prediction, &Borders::new(), &mut NodeBudget::new(0)), greedy);
+
+        // with nothing to run from it goes ahead rather than back
+        let nobody = EnemyPositionPrediction::new(&EnemyPositions::new(), &EnemyPositions::new(), Borders::new());
+        assert_eq!(lookahead_next_move(&robot_position, &nobody, &Borders::new(), &mut NodeBudget::new(0)), Some(Direction::Front));
+
+        let mut budget = NodeBudget::new(100_000);
+        let mov = lookahead_next_move(&robot_position, &prediction, &Borders::new(), &mut budget);
+        assert!(mov.is_some());
+        assert!(budget.remaining() < 100_000);
+    }
+
+    #[test]
+    fn test2() {
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(0, -3)));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(0, -2)));
+        let prediction = EnemyPositionPrediction::new(&current, &previous, Borders::new());
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+
+        // whatever the budget, the robot does not run into the enemy coming down the column
+        for nodes in [4, 20, 100, 1000, 100_000] {
+            let mov = lookahead_next_move(&robot_position, &prediction, &Borders::new(), &mut NodeBudget::new(nodes));
+            assert!(mov.is_some());
+            assert_ne!(mov, Some(Direction::Front));
+        }
+    }
+
+    #[test]
+    fn test3() {
+        // a corridor with a dead end to the east and an opening to the north just west of the robot,
+        // and an enemy coming along it from the west and bouncing between its ends
+        let corridor = || {
+            let robot_position = RobotPosition { position: Coordinate::new(2, 0), orientation: Orientation::North };
+            let mut borders = Borders::new();
+            for x in -4..=4 {
+                borders.set_border(Coordinate::new(x, 1));
+                if x != 1 {
+                    borders.set_border(Coordinate::new(x, -1));
+                }
+            }
+            borders.set_border(Coordinate::new(-4, 0));
+            borders.set_border(Coordinate::new(4, 0));
+            let mut simulator = Simulator::new(robot_position, borders, 1);
+            simulator.use_turn_chance(0);
+            simulator.use_move_model(MoveModel::Sidestep);
+            simulator.add_enemy(Coordinate::new(-3, 0), Coordinate::new(1, 0), SimulatedBehaviour::Wanderer);
+            simulator
+        };
+        let prediction_of = |s: &Simulator| EnemyPositionPrediction::new(&s.enemy_positions(), s.previous_enemy_positions(), *s.borders());
+
+        // both sidestep: greedy backs away into the dead end, looking ahead
+        // takes the opening while the enemy is still far enough
+        let greedy_ticks = corridor().run(40, |s| greedy_next_move(s.robot_position(), &prediction_of(s), s.borders()));
+        let mut simulator = corridor();
+        let lookahead_ticks = simulator.run(40, |s| lookahead_next_move(s.robot_position(), &prediction_of(s), s.borders(), &mut NodeBudget::new(2000)));
+        assert!(greedy_ticks < 10, "greedy {}", greedy_ticks);
+        assert_eq!(lookahead_ticks, 40);
+        assert!(simulator.robot_position().position.y < 0);
+    }
+}
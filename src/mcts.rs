@@ -0,0 +1,354 @@
+use crate::{borders::Borders, budget::{Budget, NodeBudget}, direction::Direction, enemy_position_prediction::EnemyPositionPrediction, evaluation::{Evaluation, Scene}, footprint::Footprint, greedy_next_move::greedy_next_move_preferring_front, rng::Rng, robot_position::{RobotPosition, SIDESTEPS}, MCTS_NODES};
+
+const NO_NODE: u16 = u16::MAX;
+// reward for every tick survived, the evaluation of where a rollout ends is added up to this
+const TICK_REWARD: i64 = 1000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EnemyPolicy {
+    // enemies move as `EnemyPositionPrediction::move_enemies` predicts
+    Prediction,
+    // enemies take random steps, which plans for the enemies doing anything the prediction misses
+    Random,
+    // every enemy steps straight at the robot, which plans for the worst
+    Chase,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MctsConfig {
+    pub iterations: u32,
+    // ticks simulated from the current position, in the tree and in the rollout together
+    pub horizon: u32,
+    // weight of the exploration term of UCT in percent
+    pub exploration: i64,
+    pub enemy_policy: EnemyPolicy,
+    pub evaluation: Evaluation,
+    pub seed: u64,
+}
+
+impl MctsConfig {
+    pub fn new() -> Self {
+        MctsConfig { iterations: 300, horizon: 8, exploration: 100, enemy_policy: EnemyPolicy::Chase, evaluation: Evaluation::new(), seed: 1 }
+    }
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    parent: u16,
+    children: [u16; 5],
+    visits: u32,
+    value: i64,
+}
+
+impl Node {
+    fn new(parent: u16) -> Self {
+        Node { parent, children: [NO_NODE; 5], visits: 0, value: 0 }
+    }
+}
+
+// Open loop Monte Carlo tree search over the robot's moves. Nodes stand for sequences of moves,
+// the enemies are simulated again on every iteration. All nodes live in a fixed pool, and the
+// subtree of the move that was taken can be kept for the next tick with `advance`.
+pub struct Mcts {
+    nodes: [Node; MCTS_NODES],
+    length: usize,
+    rng: Rng,
+    config: MctsConfig,
+}
+
+// a tiny simulation of one line of play
+struct Playout {
+    robot_position: RobotPosition,
+    prediction: EnemyPositionPrediction,
+    ticks: u32,
+    caught: bool,
+}
+
+impl Mcts {
+    pub fn new(config: MctsConfig) -> Self {
+        Mcts { nodes: [Node::new(NO_NODE); MCTS_NODES], length: 0, rng: Rng::new(config.seed), config }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.length
+    }
+
+    pub fn reset(&mut self) {
+        self.length = 0;
+    }
+
+    fn max_reward(&self) -> i64 {
+        (self.config.horizon as i64 + 1) * TICK_REWARD
+    }
+
+    fn play(&mut self, playout: &mut Playout, action: Option<Direction>) {
+        if let Some(direction) = action {
+            playout.robot_position.sidestep(direction);
+        }
+        playout.ticks += 1;
+        if playout.prediction.min_distance_from(playout.robot_position.position) == 0 {
+            playout.caught = true;
+            return;
+        }
+        match self.config.enemy_policy {
+            EnemyPolicy::Prediction => {
+                // pursuers chase the robot of the playout, not where it stood at the root
+                playout.prediction.use_pursuit_target(playout.robot_position.position);
+                playout.prediction.move_enemies()
+            }
+            EnemyPolicy::Random => playout.prediction.move_enemies_randomly(&mut self.rng),
+            EnemyPolicy::Chase => playout.prediction.move_enemies_towards(playout.robot_position.position),
+        }
+        playout.caught = playout.prediction.min_distance_from(playout.robot_position.position) == 0;
+    }
+
+    fn reward(&self, playout: &Playout, borders: &Borders) -> i64 {
+        if playout.caught {
+            (playout.ticks as i64 - 1) * TICK_REWARD
+        } else {
+            let scene = Scene::new(&playout.prediction, borders);
+            playout.ticks as i64 * TICK_REWARD + self.config.evaluation.normalized(&playout.robot_position, &Footprint::single(), &scene, TICK_REWARD)
+        }
+    }
+
+    // UCT in thousandths: the mean reward plus the exploration bonus sqrt(ln(parent visits) / visits)
+    fn uct(&self, parent_visits: u32, child: &Node) -> i64 {
+        let mean = child.value * 1000 / (child.visits as i64 * self.max_reward());
+        let ln_parent = parent_visits.max(1).ilog2() as i64 * 693;
+        mean + self.config.exploration * (ln_parent * 1000 / child.visits as i64).isqrt() / 100
+    }
+
+    fn iterate(&mut self, root_robot_position: &RobotPosition, prediction: &EnemyPositionPrediction, borders: &Borders) {
+        let mut playout = Playout { robot_position: *root_robot_position, prediction: prediction.clone(), ticks: 0, caught: false };
+        let mut node = 0;
+
+        // selection and expansion
+        while !playout.caught && playout.ticks < self.config.horizon {
+            let robot_position = playout.robot_position;
+            let legal = |a: &usize| robot_position.can_sidestep(SIDESTEPS[*a], borders);
+            let untried = (0..SIDESTEPS.len()).filter(legal).filter(|&a| self.nodes[node].children[a] == NO_NODE).count();
+
+            if untried > 0 && self.length < MCTS_NODES {
+                let pick = self.rng.below(untried as u32) as usize;
+                let action = (0..SIDESTEPS.len()).filter(legal).filter(|&a| self.nodes[node].children[a] == NO_NODE).nth(pick).unwrap();
+                self.nodes[self.length] = Node::new(node as u16);
+                self.nodes[node].children[action] = self.length as u16;
+                node = self.length;
+                self.length += 1;
+                self.play(&mut playout, SIDESTEPS[action]);
+                break;
+            }
+
+            let parent_visits = self.nodes[node].visits;
+            let best = (0..SIDESTEPS.len()).filter(legal)
+                .filter(|&a| self.nodes[node].children[a] != NO_NODE)
+                .max_by_key(|&a| self.uct(parent_visits, &self.nodes[self.nodes[node].children[a] as usize]));
+            let Some(action) = best else {
+                break;
+            };
+            node = self.nodes[node].children[action] as usize;
+            self.play(&mut playout, SIDESTEPS[action]);
+        }
+
+        // random rollout
+        while !playout.caught && playout.ticks < self.config.horizon {
+            let robot_position = playout.robot_position;
+            let legal_count = SIDESTEPS.iter().filter(|&&a| robot_position.can_sidestep(a, borders)).count();
+            let pick = self.rng.below(legal_count as u32) as usize;
+            let action = SIDESTEPS.into_iter().filter(|&a| robot_position.can_sidestep(a, borders)).nth(pick).unwrap();
+            self.play(&mut playout, action);
+        }
+
+        // backpropagation
+        let reward = self.reward(&playout, borders);
+        let mut current = node as u16;
+        while current != NO_NODE {
+            let n = &mut self.nodes[current as usize];
+            n.visits += 1;
+            n.value += reward;
+            current = n.parent;
+        }
+    }
+
+    // Searches for `config.iterations` iterations, continuing from the tree kept by `advance`, and
+    // returns the most visited move, None to stay.
+    pub fn next_move(&mut self, robot_position: &RobotPosition, prediction: &EnemyPositionPrediction, borders: &Borders) -> Option<Direction> {
+        let mut budget = NodeBudget::new(self.config.iterations);
+        self.next_move_within(robot_position, prediction, borders, &mut budget)
+    }
+
+    // Like `next_move`, but searches one iteration per node of `budget` until it runs out. Falls
+    // back to greedy with the configured evaluation, ties going to the front, when no move has been
+    // tried yet.
+    pub fn next_move_within<B: Budget>(&mut self, robot_position: &RobotPosition, prediction: &EnemyPositionPrediction, borders: &Borders, budget: &mut B) -> Option<Direction> {
+        if self.length == 0 {
+            self.nodes[0] = Node::new(NO_NODE);
+            self.length = 1;
+        }
+        while budget.spend() {
+            self.iterate(robot_position, prediction, borders);
+        }
+        match self.best_action() {
+            Some(action) => SIDESTEPS[action],
+            None => greedy_next_move_preferring_front(robot_position, &Scene::new(prediction, borders), &Footprint::single(), &self.config.evaluation),
+        }
+    }
+
+    fn best_action(&self) -> Option<usize> {
+        (0..SIDESTEPS.len()).filter(|&a| self.nodes[0].children[a] != NO_NODE)
+            .max_by_key(|&a| self.nodes[self.nodes[0].children[a] as usize].visits)
+    }
+
+    // Keeps only the subtree below `action`, to be used after the robot made that move.
+    pub fn advance(&mut self, action: Option<Direction>) {
+        let action = SIDESTEPS.iter().position(|&a| a == action).unwrap();
+        if self.length == 0 || self.nodes[0].children[action] == NO_NODE {
+            self.reset();
+            return;
+        }
+        let new_root = self.nodes[0].children[action] as usize;
+
+        // children are always stored after their parent, so one pass in order finds the subtree
+        // and moving every kept node down never overwrites a node that still has to be moved
+        let mut remap = [NO_NODE; MCTS_NODES];
+        let mut kept = 0;
+        for index in new_root..self.length {
+            let parent = self.nodes[index].parent;
+            if index == new_root || (parent != NO_NODE && remap[parent as usize] != NO_NODE) {
+                remap[index] = kept as u16;
+                kept += 1;
+            }
+        }
+        for index in new_root..self.length {
+            if remap[index] == NO_NODE {
+                continue;
+            }
+            let mut node = self.nodes[index];
+            node.parent = if index == new_root { NO_NODE } else { remap[node.parent as usize] };
+            for child in node.children.iter_mut().filter(|c| **c != NO_NODE) {
+                *child = remap[*child as usize];
+            }
+            self.nodes[remap[index] as usize] = node;
+        }
+        self.length = kept;
+    }
+}
+
+// Searches from scratch with the given configuration, for callers that do not keep a tree.
+pub fn mcts_next_move(robot_position: &RobotPosition, prediction: &EnemyPositionPrediction, borders: &Borders, config: &MctsConfig) -> Option<Direction> {
+    Mcts::new(*config).next_move(robot_position, prediction, borders)
+}
+
+#[cfg(test)]
+mod mcts_tests {
+    use core::num::NonZero;
+
+    use crate::coordinates::Coordinate;
+    use crate::enemy_position::{EnemyPosition, EnemyPositions};
+    use crate::greedy_next_move::greedy_next_move;
+    use crate::orientation::Orientation;
+    use crate::robot_position::MoveModel;
+    use crate::simulator::{SimulatedBehaviour, Simulator};
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(0, -3)));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(0, -2)));
+        let prediction = EnemyPositionPrediction::new(&current, &previous, Borders::new());
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+
+        // running into the enemy coming down the column is never the best move
+        for policy in [EnemyPolicy::Prediction, EnemyPolicy::Random, EnemyPolicy::Chase] {
+            let config = MctsConfig { enemy_policy: policy, ..MctsConfig::new() };
+            let mov = mcts_next_move(&robot_position, &prediction, &Borders::new(), &config);
+            assert!(mov.is_some());
+            assert_ne!(mov, Some(Direction::Front));
+        }
+    }
+
+    #[test]
+    fn test2() {
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(3, 3)));
+        let prediction = EnemyPositionPrediction::new(&current, &EnemyPositions::new(), Borders::new());
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+
+        let mut mcts = Mcts::new(MctsConfig::new());
+        let mov = mcts.next_move(&robot_position, &prediction, &Borders::new());
+        let before = mcts.node_count();
+        assert!(before > 1 && before <= MCTS_NODES);
+
+        // the subtree of the chosen move survives, the rest is dropped
+        mcts.advance(mov);
+        assert!(mcts.node_count() > 1);
+        assert!(mcts.node_count() < before);
+        let mut next = robot_position;
+        if let Some(mov) = mov {
+            next.sidestep(mov);
+        }
+        mcts.next_move(&next, &prediction, &Borders::new());
+        assert!(mcts.node_count() > 1);
+
+        mcts.reset();
+        assert_eq!(mcts.node_count(), 0);
+        let greedy = greedy_next_move_preferring_front(&robot_position, &Scene::new(&prediction, &Borders::new()), &Footprint::single(), &Evaluation::new());
+        assert_eq!(mcts.next_move_within(&robot_position, &prediction, &Borders::new(), &mut NodeBudget::new(0)), greedy);
+        assert_eq!(mcts.node_count(), 1);
+        mcts.reset();
+        mcts.advance(Some(Direction::Front));
+        assert_eq!(mcts.node_count(), 0);
+    }
+
+    fn arena(seed: u64) -> Simulator {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(2, 2));
+        borders.set_border(Coordinate::new(-2, -2));
+        let mut simulator = Simulator::new(robot_position, borders, seed);
+        simulator.use_move_model(MoveModel::Sidestep);
+        simulator.add_enemy(Coordinate::new(-4, -4), Coordinate::new(1, 0), SimulatedBehaviour::Wanderer);
+        simulator.add_enemy(Coordinate::new(4, 4), Coordinate::new(-1, 0), SimulatedBehaviour::Wanderer);
+        simulator.add_enemy(Coordinate::new(4, -4), Coordinate::new(0, 0), SimulatedBehaviour::Pursuer);
+        simulator.add_enemy(Coordinate::new(-4, 4), Coordinate::new(0, 0), SimulatedBehaviour::Pursuer);
+        simulator
+    }
+
+    fn prediction_of(simulator: &Simulator) -> EnemyPositionPrediction {
+        let mut prediction = EnemyPositionPrediction::new(&simulator.enemy_positions(), simulator.previous_enemy_positions(), *simulator.borders());
+        prediction.use_pursuit_target(simulator.robot_position().position);
+        prediction
+    }
+
+    #[test]
+    fn test3() {
+        // both planners sidestep, as the simulator carries their moves out
+        let (mut greedy_ticks, mut mcts_ticks, mut wins, mut losses) = (0, 0, 0, 0);
+        for seed in 1..=20 {
+            let greedy = arena(seed).run(60, |s| greedy_next_move(s.robot_position(), &prediction_of(s), s.borders()));
+
+            let mut mcts = Mcts::new(MctsConfig { seed, ..MctsConfig::new() });
+            let searched = arena(seed).run(60, |s| {
+                let mov = mcts.next_move(s.robot_position(), &prediction_of(s), s.borders());
+                mcts.advance(mov);
+                mov
+            });
+            greedy_ticks += greedy;
+            mcts_ticks += searched;
+            wins += (searched > greedy) as u32;
+            losses += (searched < greedy) as u32;
+        }
+        // with two pursuers around, planning for the enemies to chase outlasts greedy by a margin
+        assert!(mcts_ticks >= greedy_ticks + greedy_ticks / 10, "mcts {} greedy {}", mcts_ticks, greedy_ticks);
+        assert!(wins > 2 * losses, "wins {} losses {}", wins, losses);
+    }
+}
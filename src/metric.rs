@@ -0,0 +1,103 @@
+use crate::coordinates::Coordinate;
+
+// hundredths of a cell per step, used to follow octile paths without rounding at every step
+const PATH_SCALE: i32 = 100;
+const DIAGONAL_STEP: i32 = 141;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Connectivity {
+    Four, Eight
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Metric {
+    // only orthogonal steps
+    #[default]
+    Manhattan,
+    // diagonal steps cost as much as orthogonal ones
+    Chebyshev,
+    // diagonal steps cost sqrt(2), rounded to whole cells
+    Octile,
+    SquaredEuclidean,
+}
+
+impl Metric {
+    pub fn distance(&self, a: Coordinate, b: Coordinate) -> i32 {
+        match self {
+            Metric::SquaredEuclidean => {
+                let dx = a.x as i32 - b.x as i32;
+                let dy = a.y as i32 - b.y as i32;
+                dx * dx + dy * dy
+            }
+            _ => self.distance_of_path(self.path_length(a, b))
+        }
+    }
+
+    pub fn connectivity(&self) -> Connectivity {
+        match self {
+            Metric::Manhattan => Connectivity::Four,
+            _ => Connectivity::Eight
+        }
+    }
+
+    // whether every step along a path adds exactly one to the distance
+    pub(crate) fn has_unit_steps(&self) -> bool {
+        matches!(self, Metric::Manhattan | Metric::Chebyshev)
+    }
+
+    // The length of the shortest free path between a and b, in the units used to flood around borders.
+    // Squared Euclidean has no path form, its paths are measured as octile paths. They only tell which
+    // cells can be reached, the distance at the end of one is the straight squared distance.
+    pub(crate) fn path_length(&self, a: Coordinate, b: Coordinate) -> i32 {
+        let dx = a.x.abs_diff(b.x) as i32;
+        let dy = a.y.abs_diff(b.y) as i32;
+        match self {
+            Metric::Manhattan => dx + dy,
+            Metric::Chebyshev => dx.max(dy),
+            Metric::Octile | Metric::SquaredEuclidean => dx.max(dy) * PATH_SCALE + dx.min(dy) * (DIAGONAL_STEP - PATH_SCALE),
+        }
+    }
+
+    pub(crate) fn step_length(&self, diagonal: bool) -> i32 {
+        match (self, diagonal) {
+            (Metric::Manhattan | Metric::Chebyshev, _) => 1,
+            (_, false) => PATH_SCALE,
+            (_, true) => DIAGONAL_STEP,
+        }
+    }
+
+    // a path length converted to a distance in the metric
+    pub(crate) fn distance_of_path(&self, length: i32) -> i32 {
+        match self {
+            Metric::Manhattan | Metric::Chebyshev => length,
+            Metric::Octile | Metric::SquaredEuclidean => (length + PATH_SCALE / 2) / PATH_SCALE,
+        }
+    }
+
+    // a distance in the metric converted to a path length, the inverse of `distance_of_path` for whole cells
+    pub(crate) fn path_length_of(&self, distance: i32) -> i32 {
+        match self {
+            Metric::Manhattan | Metric::Chebyshev => distance,
+            Metric::Octile | Metric::SquaredEuclidean => distance.saturating_mul(PATH_SCALE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod metric_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let a = Coordinate::new(-1, 2);
+        let b = Coordinate::new(3, -1);
+        assert_eq!(Metric::Manhattan.distance(a, b), a.distance(b));
+        assert_eq!(Metric::Manhattan.distance(a, b), 7);
+        assert_eq!(Metric::Chebyshev.distance(a, b), 4);
+        // 4 + 3 * 0.41 = 5.23
+        assert_eq!(Metric::Octile.distance(a, b), 5);
+        assert_eq!(Metric::Octile.distance(Coordinate::new(0, 0), Coordinate::new(2, 2)), 3);
+        assert_eq!(Metric::SquaredEuclidean.distance(a, b), 25);
+        assert_eq!(Metric::Octile.distance(a, a), 0);
+    }
+}
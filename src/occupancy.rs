@@ -0,0 +1,111 @@
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, orientation::Orientation, N};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Occupancy {
+    Unknown, Free, Blocked
+}
+
+#[derive(Debug, Clone)]
+pub struct OccupancyMap {
+    cells: [Occupancy; N * N]
+}
+
+impl OccupancyMap {
+    pub fn new() -> Self {
+        OccupancyMap { cells: [Occupancy::Unknown; N * N] }
+    }
+
+    pub fn from_borders(borders: &Borders) -> Self {
+        let mut map = OccupancyMap { cells: [Occupancy::Free; N * N] };
+        for index in 0..(N * N) {
+            if borders.is_border(Coordinate::from_index(index).unwrap()) {
+                map.cells[index] = Occupancy::Blocked;
+            }
+        }
+        map
+    }
+
+    // everything outside of the arena is blocked
+    pub fn at(&self, coord: Coordinate) -> Occupancy {
+        match coord.to_index() {
+            Some(i) => self.cells[i],
+            None => Occupancy::Blocked
+        }
+    }
+
+    pub fn set(&mut self, coord: Coordinate, occupancy: Occupancy) {
+        if let Some(i) = coord.to_index() {
+            self.cells[i] = occupancy;
+        }
+    }
+
+    pub fn mark_free(&mut self, coord: Coordinate) {
+        self.set(coord, Occupancy::Free);
+    }
+
+    pub fn mark_blocked(&mut self, coord: Coordinate) {
+        self.set(coord, Occupancy::Blocked);
+    }
+
+    pub fn unknown_count(&self) -> usize {
+        self.cells.iter().filter(|&&c| c == Occupancy::Unknown).count()
+    }
+
+    pub fn unknown_neighbours(&self, coord: Coordinate) -> usize {
+        [Direction::Front, Direction::Right, Direction::Back, Direction::Left].into_iter()
+            .filter(|&d| self.at(coord.in_direction(d, Orientation::North)) == Occupancy::Unknown)
+            .count()
+    }
+
+    // a frontier is a known free cell next to at least one unknown cell
+    pub fn is_frontier(&self, coord: Coordinate) -> bool {
+        self.at(coord) == Occupancy::Free && self.unknown_neighbours(coord) > 0
+    }
+
+    pub fn to_borders(&self, unknown_is_border: bool) -> Borders {
+        let mut borders = Borders::new();
+        for index in 0..(N * N) {
+            let blocked = match self.cells[index] {
+                Occupancy::Blocked => true,
+                Occupancy::Unknown => unknown_is_border,
+                Occupancy::Free => false,
+            };
+            if blocked {
+                borders.set_border(Coordinate::from_index(index).unwrap());
+            }
+        }
+        borders
+    }
+}
+
+impl Default for OccupancyMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod occupancy_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut map = OccupancyMap::new();
+        assert_eq!(map.at(Coordinate::new(0, 0)), Occupancy::Unknown);
+        assert_eq!(map.at(Coordinate::new((N / 2) as i8 + 1, 0)), Occupancy::Blocked);
+        assert_eq!(map.unknown_count(), N * N);
+
+        map.mark_free(Coordinate::new(0, 0));
+        map.mark_blocked(Coordinate::new(1, 0));
+        assert!(map.is_frontier(Coordinate::new(0, 0)));
+        assert!(!map.is_frontier(Coordinate::new(1, 0)));
+        assert_eq!(map.unknown_neighbours(Coordinate::new(0, 0)), 3);
+
+        let borders = map.to_borders(false);
+        assert!(borders.is_border(Coordinate::new(1, 0)));
+        assert!(!borders.is_border(Coordinate::new(0, 1)));
+        let borders = map.to_borders(true);
+        assert!(borders.is_border(Coordinate::new(0, 1)));
+        assert!(!borders.is_border(Coordinate::new(0, 0)));
+    }
+}
@@ -0,0 +1,300 @@
+use core::num::NonZero;
+
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, enemy_position::EnemyPositions, orientation::Orientation, rng::Rng, MAX_NUM_ENEMIES, N, NUM_PARTICLES};
+
+pub const PROBABILITY_ONE: u16 = 1000;
+
+const WEIGHT_ONE: u32 = 1 << 16;
+// likelihood of an observation, out of 256, by distance between the observation and the particle,
+// particles farther away are ruled out
+const OBSERVATION_LIKELIHOOD: [u32; 3] = [256, 24, 1];
+// likelihood that an enemy in a visible cell was missed by the sensors, out of 256
+const MISSED_DETECTION_LIKELIHOOD: u32 = 4;
+
+// Probability per cell, in units of 1 / PROBABILITY_ONE.
+#[derive(Debug, Clone)]
+pub struct OccupancyDistribution {
+    probabilities: [u16; N * N]
+}
+
+impl OccupancyDistribution {
+    pub fn new() -> Self {
+        OccupancyDistribution { probabilities: [0; N * N] }
+    }
+
+    pub fn at(&self, coord: Coordinate) -> u16 {
+        match coord.to_index() {
+            Some(i) => self.probabilities[i],
+            None => 0
+        }
+    }
+
+    // probability that at least one of the enemies is in the cell, approximated by a saturating sum
+    pub fn combine(&mut self, other: &OccupancyDistribution) {
+        for (p, &q) in self.probabilities.iter_mut().zip(other.probabilities.iter()) {
+            *p = (*p + q).min(PROBABILITY_ONE);
+        }
+    }
+
+    // writes the cells with at least `threshold` probability into `cells`, returns how many were written
+    pub fn likely_cells(&self, threshold: u16, cells: &mut [Coordinate]) -> usize {
+        let mut count = 0;
+        for (index, &p) in self.probabilities.iter().enumerate() {
+            if p >= threshold && p > 0 && count < cells.len() {
+                cells[count] = Coordinate::from_index(index).unwrap();
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+impl Default for OccupancyDistribution {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParticleFilter {
+    particles: [Coordinate; NUM_PARTICLES],
+    weights: [u32; NUM_PARTICLES],
+}
+
+impl ParticleFilter {
+    pub fn new(position: Coordinate) -> Self {
+        ParticleFilter { particles: [position; NUM_PARTICLES], weights: [WEIGHT_ONE; NUM_PARTICLES] }
+    }
+
+    // Motion model: every particle stays or moves to one of its four neighbours, but never into a
+    // border or out of the arena.
+    pub fn predict(&mut self, borders: &Borders, rng: &mut Rng) {
+        for particle in &mut self.particles {
+            let next = match rng.below(5) {
+                0 => *particle,
+                1 => particle.in_direction(Direction::Front, Orientation::North),
+                2 => particle.in_direction(Direction::Right, Orientation::North),
+                3 => particle.in_direction(Direction::Back, Orientation::North),
+                _ => particle.in_direction(Direction::Left, Orientation::North),
+            };
+            if next.to_index().is_some() && !borders.is_border(next) {
+                *particle = next;
+            }
+        }
+    }
+
+    pub fn observe(&mut self, position: Coordinate, rng: &mut Rng) {
+        for (particle, weight) in self.particles.iter().zip(self.weights.iter_mut()) {
+            let likelihood = OBSERVATION_LIKELIHOOD.get(particle.distance(position) as usize).copied().unwrap_or(0);
+            *weight = *weight * likelihood / 256;
+        }
+        if self.weights.iter().all(|&w| w == 0) {
+            // no particle explains where the enemy was seen, so the filter lost it: start over there
+            *self = Self::new(position);
+            return;
+        }
+        self.normalize_and_resample(rng);
+    }
+
+    // Negative information: the enemy was not seen, so it is unlikely to be in a cell that was visible.
+    pub fn observe_absent<F: Fn(Coordinate) -> bool>(&mut self, is_visible: F, rng: &mut Rng) {
+        for (particle, weight) in self.particles.iter().zip(self.weights.iter_mut()) {
+            if is_visible(*particle) {
+                *weight = *weight * MISSED_DETECTION_LIKELIHOOD / 256;
+            }
+        }
+        self.normalize_and_resample(rng);
+    }
+
+    fn normalize_and_resample(&mut self, rng: &mut Rng) {
+        let total: u64 = self.weights.iter().map(|&w| w as u64).sum();
+        if total == 0 {
+            // every particle was ruled out, keep them but forget the weights
+            self.weights.fill(WEIGHT_ONE);
+            return;
+        }
+        for weight in &mut self.weights {
+            *weight = (*weight as u64 * WEIGHT_ONE as u64 * NUM_PARTICLES as u64 / total) as u32;
+        }
+
+        // only resample once the weights degenerated, judged by the effective sample size
+        let sum_of_squares: u64 = self.weights.iter().map(|&w| (w as u64 >> 8) * (w as u64 >> 8)).sum();
+        let total = (WEIGHT_ONE as u64 >> 8) * NUM_PARTICLES as u64;
+        if sum_of_squares > 0 && total * total / sum_of_squares >= NUM_PARTICLES as u64 / 2 {
+            return;
+        }
+        self.resample(rng);
+    }
+
+    // low variance resampling
+    fn resample(&mut self, rng: &mut Rng) {
+        let total: u64 = self.weights.iter().map(|&w| w as u64).sum();
+        let step = total / NUM_PARTICLES as u64;
+        let mut target = rng.below(step.max(1) as u32) as u64;
+        let mut cumulative = self.weights[0] as u64;
+        let mut source = 0;
+        let mut resampled = [Coordinate::new(0, 0); NUM_PARTICLES];
+
+        for particle in &mut resampled {
+            while cumulative <= target && source < NUM_PARTICLES - 1 {
+                source += 1;
+                cumulative += self.weights[source] as u64;
+            }
+            *particle = self.particles[source];
+            target += step;
+        }
+        self.particles = resampled;
+        self.weights.fill(WEIGHT_ONE);
+    }
+
+    pub fn distribution(&self) -> OccupancyDistribution {
+        let total: u64 = self.weights.iter().map(|&w| w as u64).sum();
+        let mut mass = [0u64; N * N];
+        for (particle, &weight) in self.particles.iter().zip(self.weights.iter()) {
+            if let Some(i) = particle.to_index() {
+                mass[i] += weight as u64;
+            }
+        }
+        let mut distribution = OccupancyDistribution::new();
+        for (p, m) in distribution.probabilities.iter_mut().zip(mass) {
+            *p = (m * PROBABILITY_ONE as u64).checked_div(total).unwrap_or(0) as u16;
+        }
+        distribution
+    }
+}
+
+// One particle filter per enemy id.
+pub struct EnemyTracker {
+    ids: [NonZero<u64>; MAX_NUM_ENEMIES],
+    filters: [ParticleFilter; MAX_NUM_ENEMIES],
+    length: usize,
+    rng: Rng,
+}
+
+impl EnemyTracker {
+    pub fn new(seed: u64) -> Self {
+        EnemyTracker {
+            ids: [NonZero::new(1).unwrap(); MAX_NUM_ENEMIES],
+            filters: core::array::from_fn(|_| ParticleFilter::new(Coordinate::new(0, 0))),
+            length: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    // Advances every tracked enemy by one tick and folds in the observations. Enemies that were not
+    // observed lose the belief in the cells for which `is_visible` returns true. Newly seen enemies
+    // start being tracked as long as there is space.
+    pub fn update<F: Fn(Coordinate) -> bool>(&mut self, observed: &EnemyPositions, is_visible: F, borders: &Borders) {
+        for i in 0..self.length {
+            self.filters[i].predict(borders, &mut self.rng);
+            match observed.get_position_of(self.ids[i]) {
+                Some(position) => self.filters[i].observe(position, &mut self.rng),
+                None => self.filters[i].observe_absent(&is_visible, &mut self.rng),
+            }
+        }
+
+        for enemy in observed {
+            if self.length < MAX_NUM_ENEMIES && !self.ids[..self.length].contains(&enemy.id) {
+                self.ids[self.length] = enemy.id;
+                self.filters[self.length] = ParticleFilter::new(enemy.position);
+                self.length += 1;
+            }
+        }
+    }
+
+    pub fn distribution_of(&self, id: NonZero<u64>) -> Option<OccupancyDistribution> {
+        let i = self.ids[..self.length].iter().position(|&tracked| tracked == id)?;
+        Some(self.filters[i].distribution())
+    }
+
+    pub fn distribution(&self) -> OccupancyDistribution {
+        let mut distribution = OccupancyDistribution::new();
+        for filter in &self.filters[..self.length] {
+            distribution.combine(&filter.distribution());
+        }
+        distribution
+    }
+}
+
+#[cfg(test)]
+mod particle_filter_tests {
+    use crate::enemy_position::EnemyPosition;
+    use crate::threat_map::ThreatMap;
+
+    use super::*;
+
+    fn wall() -> Borders {
+        // a wall north of the enemy, open at both ends
+        let mut borders = Borders::new();
+        for x in -2..=2 {
+            borders.set_border(Coordinate::new(x, 0));
+        }
+        borders
+    }
+
+    #[test]
+    fn test1() {
+        let borders = wall();
+        let mut rng = Rng::new(3);
+        let mut filter = ParticleFilter::new(Coordinate::new(0, 1));
+        for _ in 0..4 {
+            filter.predict(&borders, &mut rng);
+        }
+        assert!(filter.particles.iter().all(|&p| !borders.is_border(p) && p.to_index().is_some()));
+
+        let distribution = filter.distribution();
+        let total: u32 = (0..N * N).map(|i| distribution.at(Coordinate::from_index(i).unwrap()) as u32).sum();
+        assert!(total > PROBABILITY_ONE as u32 - 10 && total <= PROBABILITY_ONE as u32);
+
+        // the enemy is seen, so the belief collapses around the observation
+        filter.observe(Coordinate::new(0, 2), &mut rng);
+        let distribution = filter.distribution();
+        let most_likely = (0..N * N).map(|i| Coordinate::from_index(i).unwrap()).max_by_key(|&c| distribution.at(c)).unwrap();
+        assert_eq!(most_likely, Coordinate::new(0, 2));
+
+        // seen far from every particle, the enemy is looked for where it was seen
+        filter.observe(Coordinate::new(4, -4), &mut rng);
+        assert!(filter.particles.iter().all(|&p| p == Coordinate::new(4, -4)));
+        assert_eq!(filter.distribution().at(Coordinate::new(4, -4)), PROBABILITY_ONE);
+    }
+
+    #[test]
+    fn test2() {
+        let borders = wall();
+        let id = NonZero::new(5).unwrap();
+        let mut tracker = EnemyTracker::new(11);
+
+        let mut observed = EnemyPositions::new();
+        observed.push(EnemyPosition::new(id, Coordinate::new(0, 1)));
+        tracker.update(&observed, |_| false, &borders);
+        assert_eq!(tracker.len(), 1);
+
+        // the enemy disappears while the robot can see the whole west half of the arena
+        observed.clear();
+        for _ in 0..6 {
+            tracker.update(&observed, |c| c.x < 0, &borders);
+        }
+
+        let distribution = tracker.distribution_of(id).unwrap();
+        let west: u32 = (0..N * N).map(|i| Coordinate::from_index(i).unwrap()).filter(|c| c.x < 0).map(|c| distribution.at(c) as u32).sum();
+        let east: u32 = (0..N * N).map(|i| Coordinate::from_index(i).unwrap()).filter(|c| c.x > 0).map(|c| distribution.at(c) as u32).sum();
+        assert!(east > 4 * west);
+
+        let mut cells = [Coordinate::new(0, 0); N * N];
+        let count = tracker.distribution().likely_cells(1, &mut cells);
+        assert!(count > 1);
+        assert!(cells[..count].iter().all(|c| !borders.is_border(*c)));
+
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate_from_distribution(&tracker.distribution(), 1);
+        assert_eq!(threat_map.at(cells[0]), 0);
+    }
+}
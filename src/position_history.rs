@@ -0,0 +1,94 @@
+use crate::{coordinates::Coordinate, HISTORY_LENGTH};
+
+// The last HISTORY_LENGTH positions of the robot, to notice it going back and forth.
+#[derive(Debug, Clone, Copy)]
+pub struct PositionHistory {
+    positions: [Coordinate; HISTORY_LENGTH],
+    length: usize,
+    next: usize,
+}
+
+impl PositionHistory {
+    pub fn new() -> Self {
+        PositionHistory { positions: [Coordinate::new(0, 0); HISTORY_LENGTH], length: 0, next: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.length = 0;
+    }
+
+    pub fn push(&mut self, position: Coordinate) {
+        self.positions[self.next] = position;
+        self.next = (self.next + 1) % HISTORY_LENGTH;
+        self.length = (self.length + 1).min(HISTORY_LENGTH);
+    }
+
+    // index 0 is the oldest position
+    pub fn position(&self, i: usize) -> Coordinate {
+        self.positions[(self.next + HISTORY_LENGTH - self.length + i) % HISTORY_LENGTH]
+    }
+
+    pub fn latest(&self) -> Option<Coordinate> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.position(self.length - 1))
+        }
+    }
+
+    // how often the robot was at `position`
+    pub fn visits(&self, position: Coordinate) -> usize {
+        (0..self.length).filter(|&i| self.position(i) == position).count()
+    }
+
+    // whether the last four positions alternate between two cells
+    pub fn is_oscillating(&self) -> bool {
+        if self.length < 4 {
+            return false;
+        }
+        let last = |i: usize| self.position(self.length - 1 - i);
+        last(0) != last(1) && last(0) == last(2) && last(1) == last(3)
+    }
+}
+
+impl Default for PositionHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod position_history_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut history = PositionHistory::new();
+        assert_eq!(history.latest(), None);
+
+        let (a, b) = (Coordinate::new(0, 0), Coordinate::new(0, 1));
+        for position in [a, b, a] {
+            history.push(position);
+        }
+        assert!(!history.is_oscillating());
+        history.push(b);
+        assert!(history.is_oscillating());
+        assert_eq!(history.visits(a), 2);
+
+        // the oldest positions are forgotten
+        for _ in 0..HISTORY_LENGTH {
+            history.push(Coordinate::new(3, 3));
+        }
+        assert_eq!(history.len(), HISTORY_LENGTH);
+        assert_eq!(history.visits(a), 0);
+        assert!(!history.is_oscillating());
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, enemy_position_prediction::EnemyPositionPrediction, goal_navigation::{goal_directed_next_move, state_from_index, state_index, NavigationCosts, NUM_STATES}, robot_position::RobotPosition, threat_map::ThreatMap, MAX_NUM_ENEMIES, N};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PursuitStrategy {
+    // head for the earliest cell where the robot can meet an enemy
+    Intercept,
+    // come at the enemy from the side away from its nearest corner, so that it flees into the
+    // corner, where it is predicted to stand still
+    Corner,
+}
+
+// where and when the robot can meet the enemy with index `enemy` in the prediction
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Interception {
+    pub enemy: usize,
+    pub cell: Coordinate,
+    pub tick: i32,
+}
+
+// Plans for the rounds where the robot hunts the enemies instead of fleeing from them. The robot
+// moves like `RobotPosition::take_step`, one step or one turn per tick.
+#[derive(Debug, Clone, Copy)]
+pub struct Pursuer {
+    strategy: PursuitStrategy,
+    // how many ticks ahead interceptions are looked for
+    horizon: i32,
+    costs: NavigationCosts,
+}
+
+impl Pursuer {
+    pub fn new() -> Self {
+        // the robot does not keep away from the enemies it hunts
+        let costs = NavigationCosts { risk_aversion: 0, ..NavigationCosts::new() };
+        Pursuer { strategy: PursuitStrategy::Intercept, horizon: 2 * N as i32, costs }
+    }
+
+    pub fn use_strategy(&mut self, strategy: PursuitStrategy) {
+        self.strategy = strategy
+    }
+
+    pub fn use_horizon(&mut self, horizon: i32) {
+        self.horizon = horizon
+    }
+
+    pub fn use_navigation_costs(&mut self, costs: NavigationCosts) {
+        self.costs = costs
+    }
+
+    // The ticks the robot needs to reach every cell in any orientation, i32::MAX where it cannot go.
+    // Breadth first over (cell, orientation) states like `goal_directed_next_move`, so that turning
+    // on the spot takes a tick as well.
+    fn robot_ticks(&self, robot_position: &RobotPosition, borders: &Borders) -> [i32; N * N] {
+        let mut ticks = [i32::MAX; N * N];
+        let Some(start) = state_index(robot_position) else {
+            return ticks;
+        };
+        let mut state_ticks = [i32::MAX; NUM_STATES];
+        let mut queue = [0u16; NUM_STATES];
+        let (mut head, mut tail) = (0, 1);
+        state_ticks[start] = 0;
+        queue[0] = start as u16;
+
+        let footprint = &self.costs.footprint;
+        while head < tail {
+            let current = queue[head] as usize;
+            head += 1;
+            let state = state_from_index(current);
+            let cell = state.position.to_index().unwrap();
+            ticks[cell] = ticks[cell].min(state_ticks[current]);
+
+            for direction in [Direction::Front, Direction::Right, Direction::Back, Direction::Left] {
+                let mut next_state = state;
+                next_state.take_step(direction);
+                if !footprint.is_inside_arena(next_state.position, next_state.orientation) || footprint.collides_with(next_state.position, next_state.orientation, borders) {
+                    continue;
+                }
+                let Some(next) = state_index(&next_state) else {
+                    continue;
+                };
+                if state_ticks[next] == i32::MAX {
+                    state_ticks[next] = state_ticks[current] + 1;
+                    queue[tail] = next as u16;
+                    tail += 1;
+                }
+            }
+        }
+        ticks
+    }
+
+    // The earliest interception of every enemy within the horizon, in the order of the prediction:
+    // the first tick at which the enemy is predicted in a cell the robot can reach by then.
+    pub fn interceptions(&self, robot_position: &RobotPosition, prediction: &EnemyPositionPrediction, borders: &Borders) -> [Option<Interception>; MAX_NUM_ENEMIES] {
+        let ticks = self.robot_ticks(robot_position, borders);
+        let mut interceptions = [None; MAX_NUM_ENEMIES];
+        let mut future = prediction.clone();
+
+        for tick in 0..=self.horizon {
+            for (enemy, cell) in future.positions().enumerate() {
+                let reachable = cell.to_index().is_some_and(|i| ticks[i] <= tick);
+                if interceptions[enemy].is_none() && reachable {
+                    interceptions[enemy] = Some(Interception { enemy, cell, tick });
+                }
+            }
+            if interceptions[..future.positions().count()].iter().all(Option::is_some) {
+                break;
+            }
+            future.move_enemies();
+        }
+        interceptions
+    }
+
+    // the interception that comes first, ties go to the enemy that comes first in the prediction
+    pub fn target(&self, robot_position: &RobotPosition, prediction: &EnemyPositionPrediction, borders: &Borders) -> Option<Interception> {
+        self.interceptions(robot_position, prediction, borders).into_iter().flatten().min_by_key(|i| i.tick)
+    }
+
+    // The move towards the target, or None when the robot is where it waits for the enemy or no
+    // enemy can be reached.
+    pub fn next_move(&self, robot_position: &RobotPosition, prediction: &EnemyPositionPrediction, borders: &Borders) -> Option<Direction> {
+        let target = self.target(robot_position, prediction, borders)?;
+        let goal = match self.strategy {
+            PursuitStrategy::Intercept => target.cell,
+            PursuitStrategy::Corner => {
+                let enemy = prediction.positions().nth(target.enemy)?;
+                // once behind the enemy, or when there is no getting behind it, go for it
+                let corner = nearest_corner(enemy);
+                let robot = robot_position.position;
+                match herding_cell(enemy, borders) {
+                    Some(cell) if robot.distance(enemy) > cell.distance(enemy) || robot.distance(corner) < enemy.distance(corner) => cell,
+                    _ => target.cell,
+                }
+            }
+        };
+        goal_directed_next_move(robot_position, goal, &ThreatMap::new(), borders, &self.costs)
+    }
+}
+
+impl Default for Pursuer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the corner of the arena closest to `c`
+fn nearest_corner(c: Coordinate) -> Coordinate {
+    let half = (N / 2) as i8;
+    let toward = |v: i8| if v < 0 { -half } else { half };
+    Coordinate::new(toward(c.x), toward(c.y))
+}
+
+// The free cell next to the enemy on the side away from its nearest corner, None for an enemy that
+// is already in a corner.
+fn herding_cell(enemy: Coordinate, borders: &Borders) -> Option<Coordinate> {
+    if enemy.is_corner() {
+        return None;
+    }
+    let cell = enemy + (enemy - nearest_corner(enemy)).signum();
+    (cell.to_index().is_some() && !borders.is_border(cell)).then_some(cell)
+}
+
+#[cfg(test)]
+mod pursuit_tests {
+    use core::num::NonZero;
+
+    use crate::{enemy_position::{EnemyPosition, EnemyPositions}, orientation::Orientation, simulator::{SimulatedBehaviour, Simulator}};
+
+    use super::*;
+
+    fn prediction(current: &[Coordinate], previous: &[Coordinate], borders: Borders) -> EnemyPositionPrediction {
+        let mut current_positions = EnemyPositions::new();
+        let mut previous_positions = EnemyPositions::new();
+        for (i, &c) in current.iter().enumerate() {
+            current_positions.push(EnemyPosition::new(NonZero::new(i as u64 + 1).unwrap(), c));
+        }
+        for (i, &c) in previous.iter().enumerate() {
+            previous_positions.push(EnemyPosition::new(NonZero::new(i as u64 + 1).unwrap(), c));
+        }
+        EnemyPositionPrediction::new(&current_positions, &previous_positions, borders)
+    }
+
+    #[test]
+    fn test1() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, -1), orientation: Orientation::North };
+        let borders = Borders::new();
+        let pursuer = Pursuer::new();
+
+        // an enemy crossing in front of the robot is met on its way, not where it is now, straight
+        // ahead of the robot since turning towards it would take a tick
+        let moving = prediction(&[Coordinate::new(-3, -2)], &[Coordinate::new(-4, -2)], borders);
+        let interception = pursuer.target(&robot_position, &moving, &borders);
+        assert_eq!(interception, Some(Interception { enemy: 0, cell: Coordinate::new(0, -2), tick: 3 }));
+        let ticks = pursuer.robot_ticks(&robot_position, &borders);
+        assert_eq!(ticks[Coordinate::new(0, 0).to_index().unwrap()], 1);
+        assert_eq!(ticks[Coordinate::new(1, -1).to_index().unwrap()], 2);
+
+        // a standing enemy is met where it stands, the closer one first
+        let standing = prediction(&[Coordinate::new(2, 2), Coordinate::new(-1, 1)], &[Coordinate::new(2, 2), Coordinate::new(-1, 1)], borders);
+        let interceptions = pursuer.interceptions(&robot_position, &standing, &borders);
+        assert_eq!(interceptions[0], Some(Interception { enemy: 0, cell: Coordinate::new(2, 2), tick: 6 }));
+        assert_eq!(pursuer.target(&robot_position, &standing, &borders).map(|i| i.enemy), Some(1));
+
+        // borders make the way longer
+        let mut borders = Borders::new();
+        for x in -2..=2 {
+            borders.set_border(Coordinate::new(x, 0));
+        }
+        let standing = prediction(&[Coordinate::new(0, 1)], &[Coordinate::new(0, 1)], borders);
+        assert_eq!(pursuer.target(&robot_position, &standing, &borders).map(|i| i.tick), Some(11));
+
+        // and out of reach within a short horizon
+        let mut impatient = Pursuer::new();
+        impatient.use_horizon(10);
+        assert_eq!(impatient.target(&robot_position, &standing, &borders), None);
+        assert_eq!(impatient.next_move(&robot_position, &standing, &borders), None);
+    }
+
+    #[test]
+    fn test2() {
+        let half = (N / 2) as i8;
+        let borders = Borders::new();
+        assert_eq!(herding_cell(Coordinate::new(1, 2), &borders), Some(Coordinate::new(0, 1)));
+        assert_eq!(herding_cell(Coordinate::new(half, -half + 1), &borders), Some(Coordinate::new(half, -half + 2)));
+        assert_eq!(herding_cell(Coordinate::new(-half, half), &borders), None);
+
+        // the robot gets behind a standing enemy before going for it
+        let enemy = Coordinate::new(half - 1, half - 1);
+        let standing = prediction(&[enemy], &[enemy], borders);
+        let mut pursuer = Pursuer::new();
+        pursuer.use_strategy(PursuitStrategy::Corner);
+        let mut robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let mut herded = false;
+        for _ in 0..40 {
+            let Some(direction) = pursuer.next_move(&robot_position, &standing, &borders) else { break };
+            robot_position.take_step(direction);
+            herded |= Some(robot_position.position) == herding_cell(enemy, &borders);
+        }
+        assert!(herded);
+        assert_eq!(robot_position.position, enemy);
+    }
+
+    #[test]
+    fn test3() {
+        // a straight moving enemy is caught in the simulator
+        let mut simulator = Simulator::new(RobotPosition { position: Coordinate::new(0, 2), orientation: Orientation::North }, Borders::new(), 1);
+        simulator.use_turn_chance(0);
+        simulator.add_enemy(Coordinate::new(-3, -2), Coordinate::new(1, 0), SimulatedBehaviour::Wanderer);
+        let pursuer = Pursuer::new();
+        let ticks = simulator.run(40, |s| {
+            let current = s.enemy_positions();
+            let prediction = EnemyPositionPrediction::new(&current, s.previous_enemy_positions(), *s.borders());
+            pursuer.next_move(s.robot_position(), &prediction, s.borders())
+        });
+        assert!(simulator.is_caught());
+        assert!(ticks < 40);
+    }
+
+    #[test]
+    fn test4() {
+        // an enemy that flees the robot and hides in corners, a few cells from the nearest one
+        let half = (N / 2) as i8;
+        let chase = |strategy| {
+            let mut simulator = Simulator::new(RobotPosition { position: Coordinate::new(half - 4, half - 2), orientation: Orientation::North }, Borders::new(), 1);
+            simulator.add_enemy(Coordinate::new(half - 1, half - 2), Coordinate::new(0, 0), SimulatedBehaviour::Evader);
+            let mut pursuer = Pursuer::new();
+            pursuer.use_strategy(strategy);
+            let mut last_seen = Coordinate::new(0, 0);
+            let ticks = simulator.run(60, |s| {
+                let current = s.enemy_positions();
+                last_seen = current.iter().next().unwrap().position;
+                let prediction = EnemyPositionPrediction::new(&current, s.previous_enemy_positions(), *s.borders());
+                pursuer.next_move(s.robot_position(), &prediction, s.borders())
+            });
+            assert!(simulator.is_caught(), "{:?}", strategy);
+            (ticks, last_seen)
+        };
+
+        // herded into the nearest corner it is caught there, going straight for it chases it along
+        // the wall to the far one
+        let (corner_ticks, caught_at) = chase(PursuitStrategy::Corner);
+        assert!(caught_at.is_corner());
+        let (intercept_ticks, _) = chase(PursuitStrategy::Intercept);
+        assert!(corner_ticks < intercept_ticks, "corner {} intercept {}", corner_ticks, intercept_ticks);
+    }
+}
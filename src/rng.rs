@@ -0,0 +1,48 @@
+// Small xorshift generator, good enough for sampling and tie breaking and fully deterministic
+// for a given seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // scramble the seed (splitmix64) so that similar seeds give unrelated sequences
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        // xorshift gets stuck on a zero state
+        Rng { state: (z ^ (z >> 31)) | 1 }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 32) as u32
+    }
+
+    // uniform in 0..bound, bound must not be zero
+    pub fn below(&mut self, bound: u32) -> u32 {
+        ((self.next_u32() as u64 * bound as u64) >> 32) as u32
+    }
+}
+
+#[cfg(test)]
+mod rng_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut counts = [0; 4];
+        for _ in 0..1000 {
+            let x = a.below(4);
+            assert_eq!(x, b.below(4));
+            counts[x as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > 150));
+        assert_ne!(Rng::new(1).next_u32(), Rng::new(2).next_u32());
+    }
+}
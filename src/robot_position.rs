@@ -1,5 +1,17 @@
-use crate::{coordinates::Coordinate, direction::Direction, orientation::Orientation};
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, orientation::Orientation};
+
+// How a `Direction` is carried out. The escape planners sidestep: the robot moves into the cell
+// `in_direction` points at and keeps its heading. Navigation and the simulator turn like `take_step`:
+// Front and Back move, Left and Right only turn the robot in place.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MoveModel {
+    Sidestep, Turn
+}
+
+// what the searching escape planners try each tick: a sidestep in every direction, None stays
+pub const SIDESTEPS: [Option<Direction>; 5] = [Some(Direction::Front), Some(Direction::Right), Some(Direction::Back), Some(Direction::Left), None];
 
+#[derive(Debug, Clone, Copy)]
 pub struct RobotPosition {
     pub position: Coordinate,
     pub orientation: Orientation,
@@ -15,6 +27,26 @@ impl RobotPosition {
         }
     }
     
+    pub fn sidestep(&mut self, direction: Direction) {
+        self.position = self.in_direction(direction)
+    }
+
+    // whether sidestepping with `action` keeps the robot in the arena and off the borders, staying
+    // always does
+    pub fn can_sidestep(&self, action: Option<Direction>, borders: &Borders) -> bool {
+        action.is_none_or(|direction| {
+            let next = self.in_direction(direction);
+            next.to_index().is_some() && !borders.is_border(next)
+        })
+    }
+
+    pub fn apply(&mut self, direction: Direction, move_model: MoveModel) {
+        match move_model {
+            MoveModel::Sidestep => self.sidestep(direction),
+            MoveModel::Turn => self.take_step(direction),
+        }
+    }
+
     pub fn in_direction(&self, direction: Direction) -> Coordinate {
         self.position.in_direction(direction, self.orientation)
     }
@@ -26,6 +58,7 @@ mod test {
     use super::*;
     use crate::orientation::Orientation;
     use crate::threat_map::ThreatMap;
+    use crate::N;
 
     #[test]
     fn test1() {
@@ -100,4 +133,42 @@ mod test {
         assert_eq!(map.at(robot_position.in_direction(Direction::Right)), 1);
         assert_eq!(map.at(robot_position.in_direction(Direction::Front)), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test3() {
+        let start = RobotPosition{position: Coordinate::new(0, 0), orientation: Orientation::East};
+
+        // sidestepping to the right moves south and keeps facing east, turning only faces south
+        let mut sidestepped = start;
+        sidestepped.apply(Direction::Right, MoveModel::Sidestep);
+        assert_eq!(sidestepped.position, Coordinate::new(0, 1));
+        assert_eq!(sidestepped.orientation, Orientation::East);
+        let mut turned = start;
+        turned.apply(Direction::Right, MoveModel::Turn);
+        assert_eq!(turned.position, Coordinate::new(0, 0));
+        assert_eq!(turned.orientation, Orientation::South);
+
+        // front and back are the same in both
+        for direction in [Direction::Front, Direction::Back] {
+            let (mut a, mut b) = (start, start);
+            a.apply(direction, MoveModel::Sidestep);
+            b.apply(direction, MoveModel::Turn);
+            assert_eq!((a.position, a.orientation), (b.position, b.orientation));
+        }
+    }
+
+    #[test]
+    fn test4() {
+        let half = (N / 2) as i8;
+        let robot_position = RobotPosition{position: Coordinate::new(half, 0), orientation: Orientation::North};
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(half, -1));
+
+        // not into the border in front or off the arena to the right, staying is always possible
+        assert!(!robot_position.can_sidestep(Some(Direction::Front), &borders));
+        assert!(!robot_position.can_sidestep(Some(Direction::Right), &borders));
+        assert!(robot_position.can_sidestep(Some(Direction::Back), &borders));
+        assert!(robot_position.can_sidestep(None, &borders));
+        assert_eq!(SIDESTEPS.iter().filter(|&&a| robot_position.can_sidestep(a, &borders)).count(), 3);
+    }
+}
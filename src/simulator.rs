@@ -0,0 +1,222 @@
+use core::num::NonZero;
+
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, enemy_position::{EnemyPosition, EnemyPositions}, rng::Rng, robot_position::{MoveModel, RobotPosition}, MAX_NUM_ENEMIES};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SimulatedBehaviour {
+    // moves straight, bounces on borders and turns at random now and then
+    Wanderer,
+    // steps towards the robot
+    Pursuer,
+    // steps to the neighbouring cell farthest from the robot, and hides for good in a corner
+    Evader,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SimulatedEnemy {
+    id: NonZero<u64>,
+    position: Coordinate,
+    velocity: Coordinate,
+    behaviour: SimulatedBehaviour,
+}
+
+// A small arena to play planners against: the robot moves with `RobotPosition::take_step` unless
+// given another move model, enemies take one orthogonal step per tick and the robot is caught when
+// an enemy reaches its cell or the two pass through each other.
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    robot_position: RobotPosition,
+    enemies: [SimulatedEnemy; MAX_NUM_ENEMIES],
+    enemy_count: usize,
+    previous_enemy_positions: EnemyPositions,
+    borders: Borders,
+    rng: Rng,
+    // chance in percent that a wanderer turns in a tick
+    turn_chance: u32,
+    move_model: MoveModel,
+    tick: u32,
+    caught: bool,
+}
+
+const STEPS: [Coordinate; 4] = [Coordinate { x: 0, y: -1 }, Coordinate { x: 1, y: 0 }, Coordinate { x: 0, y: 1 }, Coordinate { x: -1, y: 0 }];
+
+impl Simulator {
+    pub fn new(robot_position: RobotPosition, borders: Borders, seed: u64) -> Self {
+        let enemy = SimulatedEnemy { id: NonZero::new(1).unwrap(), position: Coordinate::new(0, 0), velocity: Coordinate::new(0, 0), behaviour: SimulatedBehaviour::Wanderer };
+        Simulator {
+            robot_position,
+            enemies: [enemy; MAX_NUM_ENEMIES],
+            enemy_count: 0,
+            previous_enemy_positions: EnemyPositions::new(),
+            borders,
+            rng: Rng::new(seed),
+            turn_chance: 20,
+            move_model: MoveModel::Turn,
+            tick: 0,
+            caught: false,
+        }
+    }
+
+    pub fn use_turn_chance(&mut self, turn_chance: u32) {
+        self.turn_chance = turn_chance
+    }
+
+    // how the robot carries out the moves it is given, `MoveModel::Sidestep` for the escape planners,
+    // which score their moves as sidesteps
+    pub fn use_move_model(&mut self, move_model: MoveModel) {
+        self.move_model = move_model
+    }
+
+    pub fn add_enemy(&mut self, position: Coordinate, velocity: Coordinate, behaviour: SimulatedBehaviour) {
+        if self.enemy_count == MAX_NUM_ENEMIES {
+            return;
+        }
+        let id = NonZero::new(self.enemy_count as u64 + 1).unwrap();
+        self.enemies[self.enemy_count] = SimulatedEnemy { id, position, velocity, behaviour };
+        self.enemy_count += 1;
+        self.caught |= position == self.robot_position.position;
+    }
+
+    pub fn robot_position(&self) -> &RobotPosition {
+        &self.robot_position
+    }
+
+    pub fn borders(&self) -> &Borders {
+        &self.borders
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    pub fn is_caught(&self) -> bool {
+        self.caught
+    }
+
+    pub fn enemy_positions(&self) -> EnemyPositions {
+        let mut enemy_positions = EnemyPositions::new();
+        for enemy in &self.enemies[..self.enemy_count] {
+            enemy_positions.push(EnemyPosition::new(enemy.id, enemy.position));
+        }
+        enemy_positions
+    }
+
+    // where the enemies were one tick ago, as a robot would have seen them
+    pub fn previous_enemy_positions(&self) -> &EnemyPositions {
+        &self.previous_enemy_positions
+    }
+
+    fn is_free(&self, coord: Coordinate) -> bool {
+        coord.to_index().is_some() && !self.borders.is_border(coord)
+    }
+
+    // the step from `position` that gets farthest from `robot`, standing still first on ties and
+    // for good once in a corner
+    fn flee(&self, position: Coordinate, robot: Coordinate) -> Coordinate {
+        if position.is_corner() {
+            return Coordinate::new(0, 0);
+        }
+        [Coordinate::new(0, 0)].into_iter().chain(STEPS)
+            .filter(|&step| self.is_free(position + step))
+            .min_by_key(|&step| -(position + step).distance(robot))
+            .unwrap_or(Coordinate::new(0, 0))
+    }
+
+    // Plays one tick. Moves into borders or out of the arena leave the robot where it is.
+    pub fn step(&mut self, direction: Option<Direction>) {
+        if self.caught {
+            return;
+        }
+        self.previous_enemy_positions = self.enemy_positions();
+        self.tick += 1;
+
+        if let Some(direction) = direction {
+            let mut next = self.robot_position;
+            next.apply(direction, self.move_model);
+            if self.is_free(next.position) {
+                self.robot_position = next;
+            }
+        }
+        let robot_after = self.robot_position.position;
+
+        for i in 0..self.enemy_count {
+            let enemy = self.enemies[i];
+            let velocity = match enemy.behaviour {
+                SimulatedBehaviour::Pursuer => (robot_after - enemy.position).normalized(),
+                SimulatedBehaviour::Evader => self.flee(enemy.position, robot_after),
+                SimulatedBehaviour::Wanderer if enemy.velocity == Coordinate::new(0, 0) || self.rng.below(100) < self.turn_chance => {
+                    STEPS[self.rng.below(4) as usize]
+                }
+                SimulatedBehaviour::Wanderer => enemy.velocity,
+            };
+            let velocity = if self.is_free(enemy.position + velocity) { velocity } else { -velocity };
+            let position = if self.is_free(enemy.position + velocity) { enemy.position + velocity } else { enemy.position };
+
+            // the robot walked into the enemy, which also covers the two passing through each other,
+            // or the enemy walked into the robot
+            self.caught |= enemy.position == robot_after || position == robot_after;
+            self.enemies[i].position = position;
+            self.enemies[i].velocity = velocity;
+        }
+    }
+
+    // Lets `policy` steer the robot until it is caught or `max_ticks` ticks passed, and returns
+    // the number of ticks it survived.
+    pub fn run<F: FnMut(&Simulator) -> Option<Direction>>(&mut self, max_ticks: u32, mut policy: F) -> u32 {
+        while !self.caught && self.tick < max_ticks {
+            let direction = policy(self);
+            self.step(direction);
+        }
+        if self.caught { self.tick.saturating_sub(1) } else { self.tick }
+    }
+}
+
+#[cfg(test)]
+mod simulator_tests {
+    use crate::orientation::Orientation;
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let mut simulator = Simulator::new(robot_position, Borders::new(), 1);
+        simulator.add_enemy(Coordinate::new(-3, 0), Coordinate::new(1, 0), SimulatedBehaviour::Wanderer);
+        simulator.use_turn_chance(0);
+
+        simulator.step(Some(Direction::Front));
+        assert_eq!(simulator.robot_position().position, Coordinate::new(0, -1));
+        assert_eq!(simulator.enemy_positions().get_position_of(NonZero::new(1).unwrap()), Some(Coordinate::new(-2, 0)));
+        assert_eq!(simulator.previous_enemy_positions().get_position_of(NonZero::new(1).unwrap()), Some(Coordinate::new(-3, 0)));
+
+        // standing still next to the path of the enemy is safe, stepping into it is not
+        simulator.step(Some(Direction::Left));
+        assert!(!simulator.is_caught());
+        simulator.step(Some(Direction::Left));
+        simulator.step(Some(Direction::Front));
+        assert!(simulator.is_caught());
+        assert_eq!(simulator.tick(), 4);
+
+        // sidestepping to the left goes into the cell west of the robot
+        let mut simulator = Simulator::new(robot_position, Borders::new(), 1);
+        simulator.use_move_model(MoveModel::Sidestep);
+        simulator.step(Some(Direction::Left));
+        assert_eq!(simulator.robot_position().position, Coordinate::new(-1, 0));
+        assert_eq!(simulator.robot_position().orientation, Orientation::North);
+    }
+
+    #[test]
+    fn test2() {
+        let robot_position = RobotPosition { position: Coordinate::new(4, 4), orientation: Orientation::North };
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(4, 3));
+        let mut simulator = Simulator::new(robot_position, borders, 7);
+        simulator.add_enemy(Coordinate::new(0, 4), Coordinate::new(0, 0), SimulatedBehaviour::Pursuer);
+
+        // moving into the border does nothing, so the pursuer walks up to the robot
+        let survived = simulator.run(20, |_| Some(Direction::Front));
+        assert_eq!(simulator.robot_position().position, Coordinate::new(4, 4));
+        assert!(simulator.is_caught());
+        assert_eq!(survived, 3);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, enemy_position_prediction::EnemyPositionPrediction, evaluation::{Evaluation, Scene, Term}, footprint::Footprint, metric::Metric, robot_position::RobotPosition, threat_cell::ThreatCell, threat_map::ThreatMap, MAX_TEAM_SIZE};
+
+// staying put comes first, so that robots only move when it helps
+const OPTIONS: [Option<Direction>; 5] = [None, Some(Direction::Front), Some(Direction::Right), Some(Direction::Back), Some(Direction::Left)];
+
+// Plans the moves of several robots together. They share one threat map of the enemies and one set
+// of borders, and treat each other as obstacles: no two robots end up in the same cell and no two
+// robots swap cells. Moves go into the neighbouring cell like in `greedy_next_move`.
+#[derive(Debug, Clone, Copy)]
+pub struct TeamPlanner {
+    // what every robot's target is scored with, against a scene with the shared threat map
+    evaluation: Evaluation,
+    // reward per cell of distance between the two closest robots, 0 to not spread out
+    spread_weight: i32,
+}
+
+impl TeamPlanner {
+    // scores targets by the shared threat map alone
+    pub fn new() -> Self {
+        let mut evaluation = Evaluation::empty();
+        evaluation.use_weight(Term::Threat, 1);
+        TeamPlanner { evaluation, spread_weight: 0 }
+    }
+
+    pub fn use_evaluation(&mut self, evaluation: Evaluation) {
+        self.evaluation = evaluation
+    }
+
+    pub fn use_spread_weight(&mut self, spread_weight: i32) {
+        self.spread_weight = spread_weight
+    }
+
+    fn target(robot_position: &RobotPosition, option: Option<Direction>) -> Coordinate {
+        option.map_or(robot_position.position, |d| robot_position.in_direction(d))
+    }
+
+    // The best joint move, one entry per robot and None for the robots that should stay. Only the
+    // first MAX_TEAM_SIZE robots are planned for. The joint move first keeps the robot with the
+    // worst evaluation as well off as possible, then the team as a whole.
+    pub fn next_moves<T: ThreatCell>(&self, robots: &[RobotPosition], prediction: &EnemyPositionPrediction, threat_map: &ThreatMap<T>, borders: &Borders) -> [Option<Direction>; MAX_TEAM_SIZE] {
+        let robots = &robots[..robots.len().min(MAX_TEAM_SIZE)];
+        let scene = Scene::new(prediction, borders).with_threat_map(threat_map);
+        let mut best = [None; MAX_TEAM_SIZE];
+        let mut best_score = (i64::MIN, i64::MIN);
+
+        let combinations = OPTIONS.len().pow(robots.len() as u32);
+        for combination in 0..combinations {
+            let mut moves = [None; MAX_TEAM_SIZE];
+            let mut targets = [Coordinate::new(0, 0); MAX_TEAM_SIZE];
+            let mut rest = combination;
+            for (i, robot) in robots.iter().enumerate() {
+                moves[i] = OPTIONS[rest % OPTIONS.len()];
+                rest /= OPTIONS.len();
+                targets[i] = Self::target(robot, moves[i]);
+            }
+            if !self.is_allowed(robots, &targets[..robots.len()], borders) {
+                continue;
+            }
+            let score = self.score(robots, &targets[..robots.len()], &scene);
+            if score > best_score {
+                best = moves;
+                best_score = score;
+            }
+        }
+        best
+    }
+
+    fn is_allowed(&self, robots: &[RobotPosition], targets: &[Coordinate], borders: &Borders) -> bool {
+        for (i, &target) in targets.iter().enumerate() {
+            if target.to_index().is_none() || borders.is_border(target) {
+                return false;
+            }
+            for j in 0..i {
+                let same_cell = targets[j] == target;
+                let swap = targets[j] == robots[i].position && robots[j].position == target;
+                if same_cell || swap {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // The evaluation of the robot that ends up worst off, then the evaluations summed over the team
+    // plus how far the two closest robots are apart. Robots keep their heading as they move.
+    fn score<T: ThreatCell>(&self, robots: &[RobotPosition], targets: &[Coordinate], scene: &Scene<T>) -> (i64, i64) {
+        let evaluate = |(robot, &position): (&RobotPosition, &Coordinate)| {
+            self.evaluation.evaluate(&RobotPosition { position, orientation: robot.orientation }, &Footprint::single(), scene)
+        };
+        let worst = robots.iter().zip(targets).map(evaluate).min().unwrap_or(0);
+        let total: i64 = robots.iter().zip(targets).map(evaluate).sum();
+
+        let closest_pair = (0..targets.len())
+            .flat_map(|i| (0..i).map(move |j| (i, j)))
+            .map(|(i, j)| Metric::Manhattan.distance(targets[i], targets[j]))
+            .min()
+            .unwrap_or(0);
+        (worst, total + self.spread_weight as i64 * closest_pair as i64)
+    }
+}
+
+impl Default for TeamPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod team_tests {
+    use core::num::NonZero;
+
+    use crate::enemy_position::{EnemyPosition, EnemyPositions};
+    use crate::greedy_next_move::greedy_next_move;
+    use crate::orientation::Orientation;
+
+    use super::*;
+
+    fn robot(x: i8, y: i8) -> RobotPosition {
+        RobotPosition { position: Coordinate::new(x, y), orientation: Orientation::North }
+    }
+
+    fn standing(enemies: &[Coordinate], borders: Borders) -> EnemyPositionPrediction {
+        let mut positions = EnemyPositions::new();
+        for (i, &c) in enemies.iter().enumerate() {
+            positions.push(EnemyPosition::new(NonZero::new(i as u64 + 1).unwrap(), c));
+        }
+        EnemyPositionPrediction::new(&positions, &positions, borders)
+    }
+
+    #[test]
+    fn test1() {
+        // two robots in dead ends on either side of the one cell farther from both enemies
+        let mut borders = Borders::new();
+        for c in [(-1, -1), (-1, 1), (-2, 0), (1, -1), (1, 1), (2, 0)] {
+            borders.set_border(Coordinate::new(c.0, c.1));
+        }
+        let enemies = [Coordinate::new(-4, 0), Coordinate::new(4, 0)];
+        let prediction = standing(&enemies, borders);
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate(&enemies);
+        let robots = [robot(-1, 0), robot(1, 0)];
+
+        // planned on their own both would take it
+        assert_eq!(threat_map.distance_at(Coordinate::new(0, 0)), 4);
+        assert_eq!(threat_map.distance_at(robots[0].position), 3);
+        for robot in &robots {
+            let alone = greedy_next_move(robot, &prediction, &borders).unwrap();
+            assert_eq!(robot.in_direction(alone), Coordinate::new(0, 0));
+        }
+
+        let moves = TeamPlanner::new().next_moves(&robots, &prediction, &threat_map, &borders);
+        let targets = [TeamPlanner::target(&robots[0], moves[0]), TeamPlanner::target(&robots[1], moves[1])];
+        assert_ne!(targets[0], targets[1]);
+        assert_eq!(targets.iter().filter(|&&t| t == Coordinate::new(0, 0)).count(), 1);
+        assert_eq!(moves[2..], [None, None]);
+    }
+
+    #[test]
+    fn test2() {
+        let borders = Borders::new();
+        let nobody = standing(&[], borders);
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate_with_borders(&[], &borders);
+        let robots = [robot(0, 0), robot(1, 0)];
+
+        // with no enemy around nothing is worth moving for, unless the robots should spread out
+        let mut planner = TeamPlanner::new();
+        assert_eq!(planner.next_moves(&robots, &nobody, &threat_map, &borders), [None; MAX_TEAM_SIZE]);
+        planner.use_spread_weight(1);
+        let moves = planner.next_moves(&robots, &nobody, &threat_map, &borders);
+        let a = TeamPlanner::target(&robots[0], moves[0]);
+        let b = TeamPlanner::target(&robots[1], moves[1]);
+        assert_eq!(Metric::Manhattan.distance(a, b), 3);
+
+        // an evaluation of their own, here keeping to the centre, moves the robots just the same
+        let mut evaluation = Evaluation::empty();
+        evaluation.use_weight(Term::CentreDistance, -1);
+        let mut planner = TeamPlanner::new();
+        planner.use_evaluation(evaluation);
+        let robots = [robot(2, 0), robot(-2, 0)];
+        let moves = planner.next_moves(&robots, &nobody, &threat_map, &borders);
+        assert_eq!(TeamPlanner::target(&robots[0], moves[0]), Coordinate::new(1, 0));
+        assert_eq!(TeamPlanner::target(&robots[1], moves[1]), Coordinate::new(-1, 0));
+
+        // robots facing each other never swap cells
+        let robots = [robot(0, 0), RobotPosition { position: Coordinate::new(0, -1), orientation: Orientation::South }];
+        let prediction = standing(&[Coordinate::new(0, 4)], borders);
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate(&[Coordinate::new(0, 4)]);
+        let moves = TeamPlanner::new().next_moves(&robots, &prediction, &threat_map, &borders);
+        assert!(!(moves[0] == Some(Direction::Front) && moves[1] == Some(Direction::Front)));
+    }
+}
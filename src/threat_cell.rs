@@ -0,0 +1,118 @@
+// Storage type for one cell of a threat map. Distances that do not fit saturate to MAX, which is also
+// the value of cells that no enemy can reach.
+pub trait ThreatCell: Copy + Ord + core::fmt::Debug {
+    const MAX: Self;
+    const ZERO: Self;
+
+    fn from_distance(distance: i32) -> Self;
+
+    // MAX converts to i32::MAX
+    fn to_distance(self) -> i32;
+}
+
+impl ThreatCell for i32 {
+    const MAX: Self = i32::MAX;
+    const ZERO: Self = 0;
+
+    fn from_distance(distance: i32) -> Self {
+        distance
+    }
+
+    fn to_distance(self) -> i32 {
+        self
+    }
+}
+
+impl ThreatCell for u16 {
+    const MAX: Self = u16::MAX;
+    const ZERO: Self = 0;
+
+    fn from_distance(distance: i32) -> Self {
+        distance.clamp(0, u16::MAX as i32) as u16
+    }
+
+    fn to_distance(self) -> i32 {
+        if self == u16::MAX { i32::MAX } else { self as i32 }
+    }
+}
+
+impl ThreatCell for u8 {
+    const MAX: Self = u8::MAX;
+    const ZERO: Self = 0;
+
+    fn from_distance(distance: i32) -> Self {
+        distance.clamp(0, u8::MAX as i32) as u8
+    }
+
+    fn to_distance(self) -> i32 {
+        if self == u8::MAX { i32::MAX } else { self as i32 }
+    }
+}
+
+// Unsigned 8.8 fixed point, for threat that is weighted or probabilistic rather than a whole
+// number of cells.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct Fixed(u16);
+
+impl Fixed {
+    pub const FRACTIONAL_BITS: u32 = 8;
+    pub const ONE: Fixed = Fixed(1 << Self::FRACTIONAL_BITS);
+
+    pub fn from_raw(raw: u16) -> Self {
+        Fixed(raw)
+    }
+
+    pub fn raw(self) -> u16 {
+        self.0
+    }
+
+    // numerator / denominator, saturating
+    pub fn from_ratio(numerator: u32, denominator: u32) -> Self {
+        let raw = ((numerator as u64) << Self::FRACTIONAL_BITS).checked_div(denominator as u64).unwrap_or(u16::MAX as u64);
+        Fixed(raw.min(u16::MAX as u64) as u16)
+    }
+
+    pub fn saturating_add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_mul(self, other: Fixed) -> Fixed {
+        let raw = (self.0 as u32 * other.0 as u32) >> Self::FRACTIONAL_BITS;
+        Fixed(raw.min(u16::MAX as u32) as u16)
+    }
+}
+
+impl ThreatCell for Fixed {
+    const MAX: Self = Fixed(u16::MAX);
+    const ZERO: Self = Fixed(0);
+
+    fn from_distance(distance: i32) -> Self {
+        Fixed(((distance.max(0) as u32) << Self::FRACTIONAL_BITS).min(u16::MAX as u32) as u16)
+    }
+
+    // rounds down to whole cells
+    fn to_distance(self) -> i32 {
+        if self == Self::MAX { i32::MAX } else { (self.0 >> Self::FRACTIONAL_BITS) as i32 }
+    }
+}
+
+#[cfg(test)]
+mod threat_cell_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        assert_eq!(u8::from_distance(300), u8::MAX);
+        assert_eq!(u8::MAX.to_distance(), i32::MAX);
+        assert_eq!(u8::from_distance(-3), 0);
+        assert_eq!(u16::from_distance(300).to_distance(), 300);
+        assert_eq!(i32::from_distance(i32::MAX), i32::MAX);
+
+        assert_eq!(Fixed::from_distance(3).to_distance(), 3);
+        assert_eq!(Fixed::from_distance(300), Fixed::MAX);
+        assert_eq!(Fixed::from_ratio(3, 2).saturating_mul(Fixed::from_distance(2)), Fixed::from_distance(3));
+        assert_eq!(Fixed::from_ratio(1, 2).saturating_add(Fixed::from_ratio(1, 2)), Fixed::ONE);
+        assert!(Fixed::from_ratio(5, 2) > Fixed::from_distance(2));
+        assert_eq!(Fixed::from_ratio(5, 2).to_distance(), 2);
+    }
+}
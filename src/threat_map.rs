@@ -1,39 +1,94 @@
+use crate::borders::Borders;
 use crate::coordinates::Coordinate;
 use crate::enemy_position::EnemyPositions;
-use crate::N;
+use crate::flood::flood;
+use crate::metric::{Connectivity, Metric};
+use crate::particle_filter::OccupancyDistribution;
+use crate::threat_cell::ThreatCell;
+use crate::{MAX_NUM_ENEMIES, N};
 
-pub struct ThreatMap {
-    map: [i32; N * N],
+// The cell type defaults to i32, smaller types save memory on the robot and saturate at their maximum.
+pub struct ThreatMap<T: ThreatCell = i32> {
+    map: [T; N * N],
+    metric: Metric,
 }
 
 impl ThreatMap {
     pub fn new() -> Self {
-        ThreatMap { map: [i32::MAX; N * N] }
+        Self::empty()
+    }
+}
+
+impl<T: ThreatCell> ThreatMap<T> {
+    pub fn empty() -> Self {
+        ThreatMap { map: [T::MAX; N * N], metric: Metric::Manhattan }
+    }
+
+    // how distances to enemies are measured, Manhattan by default
+    pub fn use_metric(&mut self, metric: Metric) {
+        self.metric = metric
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
     }
 
     fn reset(&mut self) {
-        self.map.fill(i32::MAX);
+        self.map.fill(T::MAX);
     }
 
-    pub fn at(&self, coords: Coordinate) -> i32 {
+    pub fn at(&self, coords: Coordinate) -> T {
         match coords.to_index() {
             Some(i) => self.map[i],
-            None => 0
+            None => T::ZERO
         }
     }
 
+    // the value at `coords` in cells, i32::MAX where no enemy can reach
+    pub fn distance_at(&self, coords: Coordinate) -> i32 {
+        self.at(coords).to_distance()
+    }
+
     pub fn calculate(&mut self, bot_coords: &[Coordinate]) {
         self.reset();
 
         for index in 0..(N * N) {
             let current_coord = Coordinate::from_index(index).unwrap();
             for &bot_coord in bot_coords {
-                let distance = current_coord.distance(bot_coord);
+                let distance = T::from_distance(self.metric.distance(current_coord, bot_coord));
                 self.map[index] = self.map[index].min(distance)
             }
         }
     }
 
+    // Like `calculate`, but distances are measured along paths that go around borders. Border cells
+    // are masked, cells that no enemy can reach keep the maximum value.
+    pub fn calculate_with_borders(&mut self, bot_coords: &[Coordinate], borders: &Borders) {
+        self.reset();
+        let metric = self.metric;
+        if metric == Metric::SquaredEuclidean {
+            for &coord in bot_coords {
+                self.merge_enemy(coord, borders, |distance| distance);
+            }
+        } else {
+            flood(&mut self.map, bot_coords.iter().flat_map(|&c| flood_seeds(c, metric)), |i| !borders.is_border(Coordinate::from_index(i).unwrap()), metric);
+        }
+        self.mask_borders(borders);
+    }
+
+    pub(crate) fn cells_mut(&mut self) -> &mut [T; N * N] {
+        &mut self.map
+    }
+
+    pub(crate) fn mask_borders(&mut self, borders: &Borders) {
+        for index in 0..(N * N) {
+            let coord = Coordinate::from_index(index).unwrap();
+            if borders.is_border(coord) {
+                self.mask_border(coord);
+            }
+        }
+    }
+
     pub fn calculate_with_previous_location(&mut self, current_enemy_positions: &EnemyPositions, previous_enemy_positions: &EnemyPositions) {
         let mut future_enemy_positions = [Coordinate::new(0, 0); N * N];
         let mut future_enemy_count = 0;
@@ -42,7 +97,7 @@ impl ThreatMap {
                 Some(previous_position) => {
                     let future_position = current_position.position + current_position.position - previous_position;
                     // we take the worst case scenario of the closest point to us
-                    if future_position.distance(Coordinate::new(0, 0)) < current_position.position.distance(Coordinate::new(0, 0)) { 
+                    if self.metric.distance(future_position, Coordinate::new(0, 0)) < self.metric.distance(current_position.position, Coordinate::new(0, 0)) {
                         future_enemy_positions[future_enemy_count] = future_position;
                     } else {
                         future_enemy_positions[future_enemy_count] = current_position.position;
@@ -59,15 +114,115 @@ impl ThreatMap {
         self.calculate(&future_enemy_positions[..future_enemy_count]);
     }
 
+    // Threat measured in ticks instead of cells: how many ticks the fastest enemy needs to reach each
+    // cell, given every enemy as its position and its speed in cells per tick.
+    pub fn calculate_ticks_to_reach(&mut self, enemies: &[(Coordinate, i32)], borders: &Borders) {
+        self.reset();
+        for &(position, speed) in enemies {
+            self.merge_enemy(position, borders, |distance| ticks_to_reach(distance, speed));
+        }
+        self.mask_borders(borders);
+    }
+
+    // Like `calculate_with_borders`, but every enemy counts by its attributes: its distances shrink by
+    // its danger radius and are scaled by its weight.
+    pub fn calculate_weighted(&mut self, enemy_positions: &EnemyPositions, borders: &Borders) {
+        self.reset();
+        for enemy in enemy_positions {
+            self.merge_enemy(enemy.position, borders, |distance| enemy.attributes.effective_distance(distance));
+        }
+        self.mask_borders(borders);
+    }
+
+    // floods the distances of one enemy and keeps the lowest value of each cell after `value_of`
+    fn merge_enemy<F: Fn(i32) -> i32>(&mut self, position: Coordinate, borders: &Borders, value_of: F) {
+        let distances = enemy_distances(position, borders, self.metric);
+        for (value, &distance) in self.map.iter_mut().zip(distances.iter()) {
+            *value = (*value).min(T::from_distance(value_of(distance)));
+        }
+    }
+
+    // Like `calculate_with_previous_location`, but every enemy keeps the speed it was last seen moving at,
+    // at least one cell per tick, and the map holds ticks to reach. The speed is measured in the metric
+    // of the map, so that it divides distances of the same kind.
+    pub fn calculate_ticks_with_previous_location(&mut self, current_enemy_positions: &EnemyPositions, previous_enemy_positions: &EnemyPositions, borders: &Borders) {
+        let mut enemies = [(Coordinate::new(0, 0), 1); MAX_NUM_ENEMIES];
+        for (enemy, current_position) in enemies.iter_mut().zip(current_enemy_positions) {
+            let speed = match previous_enemy_positions.get_position_of(current_position.id) {
+                // an enemy that stood still can still start moving
+                Some(previous_position) => self.metric.distance(previous_position, current_position.position).max(1),
+                None => 1,
+            };
+            *enemy = (current_position.position, speed);
+        }
+        self.calculate_ticks_to_reach(&enemies[..current_enemy_positions.len()], borders);
+    }
+
+    // every cell in which an enemy is at least `threshold` likely counts as an enemy position
+    pub fn calculate_from_distribution(&mut self, distribution: &OccupancyDistribution, threshold: u16) {
+        let mut likely_positions = [Coordinate::new(0, 0); N * N];
+        let count = distribution.likely_cells(threshold, &mut likely_positions);
+        self.calculate(&likely_positions[..count]);
+    }
+
     pub fn mask_border(&mut self, border_coord: Coordinate) {
         if let Some(index) = border_coord.to_index() {
-            self.map[index] = 0;
+            self.map[index] = T::ZERO;
         }
     }
 }
 
+// Enemies outside of the map enter it already that far away along a path. With four neighbours the
+// closest cell is always the best way in, with diagonal steps any cell on the edge of the map can be.
+pub(crate) fn flood_seeds(coord: Coordinate, metric: Metric) -> impl Iterator<Item = (usize, i32)> {
+    let bound = (N as i8) / 2;
+    let entry = Coordinate::new(coord.x.clamp(-bound, bound), coord.y.clamp(-bound, bound));
+    let any_edge = coord != entry && metric.connectivity() == Connectivity::Eight;
+    (0..(N * N)).filter_map(move |index| {
+        let cell = Coordinate::from_index(index).unwrap();
+        let is_entry = if any_edge { cell.x.abs() == bound || cell.y.abs() == bound } else { cell == entry };
+        is_entry.then(|| (index, metric.path_length(coord, cell)))
+    })
+}
+
+// The distances of one enemy along paths around the borders. Squared Euclidean has no path form, so
+// the cells the enemy can reach get their straight squared distance to it.
+fn enemy_distances(position: Coordinate, borders: &Borders, metric: Metric) -> [i32; N * N] {
+    let passable = |i| !borders.is_border(Coordinate::from_index(i).unwrap());
+    let mut distances = [i32::MAX; N * N];
+    flood(&mut distances, flood_seeds(position, metric), passable, metric);
+    if metric == Metric::SquaredEuclidean {
+        for (index, distance) in distances.iter_mut().enumerate().filter(|(_, d)| **d != i32::MAX) {
+            *distance = metric.distance(position, Coordinate::from_index(index).unwrap());
+        }
+    }
+    distances
+}
+
+// whole ticks an enemy moving `speed` cells per tick needs to cover `distance`, an enemy that does not
+// move never arrives
+pub fn ticks_to_reach(distance: i32, speed: i32) -> i32 {
+    match (distance, speed) {
+        (0, _) => 0,
+        (i32::MAX, _) => i32::MAX,
+        (_, speed) if speed <= 0 => i32::MAX,
+        (distance, speed) => (distance + speed - 1) / speed,
+    }
+}
+
+impl<T: ThreatCell> Default for ThreatMap<T> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
 #[cfg(test)]
 mod threat_map_tests {
+    use core::num::NonZero;
+
+    use crate::enemy_position::{EnemyAttributes, EnemyKind, EnemyPosition};
+    use crate::threat_cell::Fixed;
+
     use super::*;
 
     #[test]
@@ -111,4 +266,169 @@ mod threat_map_tests {
 
         assert_eq!(threat_map.at(Coordinate::new(1, -1)), 4);
     }
+
+    #[test]
+    fn test2() {
+        let enemies = [Coordinate::new(-1, 1), Coordinate::new(3, -2), Coordinate::new(7, 1), Coordinate::new(-6, -9)];
+        let mut expected = ThreatMap::new();
+        expected.calculate(&enemies);
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate_with_borders(&enemies, &Borders::new());
+        assert_eq!(threat_map.map, expected.map);
+
+        // a wall across the arena with a gap at its east end
+        let half = (N / 2) as i8;
+        let mut borders = Borders::new();
+        for x in -half..half {
+            borders.set_border(Coordinate::new(x, 0));
+        }
+        threat_map.calculate_with_borders(&[Coordinate::new(0, -2)], &borders);
+        assert_eq!(threat_map.at(Coordinate::new(0, 0)), 0);
+        assert_eq!(threat_map.at(Coordinate::new(0, -1)), 1);
+        assert_eq!(threat_map.at(Coordinate::new(0, 1)), 2 * half as i32 + 3);
+        assert_eq!(threat_map.at(Coordinate::new(-half, 1)), 3 * half as i32 + 3);
+    }
+
+    #[test]
+    fn test3() {
+        let enemies = [Coordinate::new(-1, 1), Coordinate::new(30, -2)];
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(0, 1));
+
+        let mut wide: ThreatMap = ThreatMap::new();
+        let mut small = ThreatMap::<u8>::empty();
+        let mut fixed = ThreatMap::<Fixed>::empty();
+        wide.calculate_with_borders(&enemies, &borders);
+        small.calculate_with_borders(&enemies, &borders);
+        fixed.calculate_with_borders(&enemies, &borders);
+        for index in 0..(N * N) {
+            let coord = Coordinate::from_index(index).unwrap();
+            assert_eq!(small.distance_at(coord), wide.at(coord));
+            assert_eq!(fixed.distance_at(coord), wide.at(coord));
+        }
+        assert_eq!(core::mem::size_of::<ThreatMap<u8>>() * 4, core::mem::size_of::<ThreatMap>());
+
+        // an enemy too far away for a u8 saturates
+        small.calculate(&[Coordinate::new(127, 127)]);
+        assert_eq!(small.at(Coordinate::new(-4, -4)), u8::MAX);
+        assert_eq!(small.distance_at(Coordinate::new(-4, -4)), i32::MAX);
+        assert_eq!(small.at(Coordinate::new(4, 4)), 246);
+    }
+
+    #[test]
+    fn test4() {
+        let enemies = [Coordinate::new(-1, 1), Coordinate::new(6, -2)];
+        for metric in [Metric::Manhattan, Metric::Chebyshev, Metric::Octile, Metric::SquaredEuclidean] {
+            let mut expected = ThreatMap::new();
+            expected.use_metric(metric);
+            expected.calculate(&enemies);
+            let mut threat_map = ThreatMap::new();
+            threat_map.use_metric(metric);
+            threat_map.calculate_with_borders(&enemies, &Borders::new());
+            assert_eq!(threat_map.map, expected.map, "{:?}", metric);
+        }
+
+        let mut threat_map = ThreatMap::new();
+        threat_map.use_metric(Metric::Chebyshev);
+        threat_map.calculate(&[Coordinate::new(0, 0)]);
+        assert_eq!(threat_map.at(Coordinate::new(2, -2)), 2);
+        threat_map.use_metric(Metric::SquaredEuclidean);
+        threat_map.calculate(&[Coordinate::new(0, 0)]);
+        assert_eq!(threat_map.at(Coordinate::new(2, -1)), 5);
+
+        // with eight neighbours the only gap in a wall is reached diagonally
+        let mut borders = Borders::new();
+        for x in -4..=4 {
+            if x != 1 {
+                borders.set_border(Coordinate::new(x, 0));
+            }
+        }
+        threat_map.use_metric(Metric::Chebyshev);
+        threat_map.calculate_with_borders(&[Coordinate::new(0, -1)], &borders);
+        assert_eq!(threat_map.at(Coordinate::new(1, 0)), 1);
+        assert_eq!(threat_map.at(Coordinate::new(0, 1)), 2);
+        threat_map.use_metric(Metric::Manhattan);
+        threat_map.calculate_with_borders(&[Coordinate::new(0, -1)], &borders);
+        assert_eq!(threat_map.at(Coordinate::new(0, 1)), 4);
+
+        // squared distances are straight ones, the borders only decide which cells are reached
+        threat_map.use_metric(Metric::SquaredEuclidean);
+        threat_map.calculate_with_borders(&[Coordinate::new(0, -1)], &borders);
+        assert_eq!(threat_map.at(Coordinate::new(2, 1)), 8);
+        let half = (N / 2) as i8;
+        for x in -half..=half {
+            borders.set_border(Coordinate::new(x, 0));
+        }
+        threat_map.calculate_with_borders(&[Coordinate::new(0, -1)], &borders);
+        assert_eq!(threat_map.at(Coordinate::new(2, 1)), i32::MAX);
+        assert_eq!(threat_map.at(Coordinate::new(2, -1)), 4);
+
+        // many enemies outside of the map, each one closer than the one before, seed the same edge
+        let far: [Coordinate; 115] = core::array::from_fn(|i| Coordinate::new(119 - i as i8, 119 - i as i8));
+        let mut expected = ThreatMap::new();
+        expected.use_metric(Metric::Chebyshev);
+        expected.calculate(&far);
+        threat_map.use_metric(Metric::Chebyshev);
+        threat_map.calculate_with_borders(&far, &Borders::new());
+        assert_eq!(threat_map.map, expected.map);
+    }
+
+    #[test]
+    fn test5() {
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(1, 1));
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate_ticks_to_reach(&[(Coordinate::new(0, 0), 2), (Coordinate::new(4, 4), 1)], &borders);
+        assert_eq!(threat_map.at(Coordinate::new(0, 0)), 0);
+        assert_eq!(threat_map.at(Coordinate::new(0, 1)), 1);
+        assert_eq!(threat_map.at(Coordinate::new(-3, 0)), 2);
+        assert_eq!(threat_map.at(Coordinate::new(-4, 0)), 2);
+        assert_eq!(threat_map.at(Coordinate::new(4, 3)), 1);
+        assert_eq!(threat_map.at(Coordinate::new(1, 1)), 0);
+
+        let id = NonZero::new(1).unwrap();
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(id, Coordinate::new(-4, 0)));
+        let mut current = EnemyPositions::new();
+        current.push(EnemyPosition::new(id, Coordinate::new(-1, 0)));
+        threat_map.calculate_ticks_with_previous_location(&current, &previous, &Borders::new());
+        assert_eq!(threat_map.at(Coordinate::new(4, 0)), 2);
+        assert_eq!(threat_map.at(Coordinate::new(2, 0)), 1);
+        assert_eq!(ticks_to_reach(5, 0), i32::MAX);
+
+        // a standing enemy counts as moving one cell per tick
+        threat_map.calculate_ticks_with_previous_location(&current, &current, &Borders::new());
+        assert_eq!(threat_map.at(Coordinate::new(2, 0)), 3);
+
+        // a diagonal step covers two cells in Manhattan distance, but one in Chebyshev
+        let mut previous = EnemyPositions::new();
+        previous.push(EnemyPosition::new(id, Coordinate::new(-2, -1)));
+        threat_map.calculate_ticks_with_previous_location(&current, &previous, &Borders::new());
+        assert_eq!(threat_map.at(Coordinate::new(3, 0)), 2);
+        threat_map.use_metric(Metric::Chebyshev);
+        threat_map.calculate_ticks_with_previous_location(&current, &previous, &Borders::new());
+        assert_eq!(threat_map.at(Coordinate::new(3, 0)), 4);
+    }
+
+    #[test]
+    fn test6() {
+        let mut enemies = EnemyPositions::new();
+        enemies.push(EnemyPosition::with_attributes(NonZero::new(1).unwrap(), Coordinate::new(-4, 0), EnemyAttributes::of_kind(EnemyKind::Rammer)));
+        enemies.push(EnemyPosition::with_attributes(NonZero::new(2).unwrap(), Coordinate::new(4, 0), EnemyAttributes::of_kind(EnemyKind::Drone)));
+        let mut threat_map = ThreatMap::new();
+        threat_map.calculate_weighted(&enemies, &Borders::new());
+
+        assert_eq!(threat_map.at(Coordinate::new(-3, 0)), 0);
+        assert_eq!(threat_map.at(Coordinate::new(0, 0)), 1);
+        assert_eq!(threat_map.at(Coordinate::new(3, 0)), 2);
+        assert_eq!(threat_map.at(Coordinate::new(4, 0)), 0);
+
+        // with default attributes it is the plain distance
+        let mut plain = EnemyPositions::new();
+        plain.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(-4, 0)));
+        threat_map.calculate_weighted(&plain, &Borders::new());
+        let mut expected = ThreatMap::new();
+        expected.calculate(&[Coordinate::new(-4, 0)]);
+        assert_eq!(threat_map.map, expected.map);
+    }
 }
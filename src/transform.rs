@@ -0,0 +1,153 @@
+use crate::{borders::Borders, coordinates::Coordinate, enemy_position::EnemyPositions, orientation::Orientation, robot_position::RobotPosition};
+
+// A rigid transform of the grid: rotate by `orientation`, then move by `translation`.
+// The rotation turns the local Front (y - 1, as in `Coordinate::in_direction`) to face `orientation`,
+// so the transform of a robot pose maps cells seen from the robot into the frame the pose lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transform {
+    pub translation: Coordinate,
+    pub orientation: Orientation,
+}
+
+impl Transform {
+    pub fn new(translation: Coordinate, orientation: Orientation) -> Self {
+        Transform { translation, orientation }
+    }
+
+    pub fn identity() -> Self {
+        Transform::new(Coordinate::new(0, 0), Orientation::North)
+    }
+
+    // maps cells relative to the robot to cells of the frame the robot position is given in
+    pub fn robot_to_world(robot_position: &RobotPosition) -> Self {
+        Transform::new(robot_position.position, robot_position.orientation)
+    }
+
+    // maps cells of the frame the robot position is given in to cells relative to the robot
+    pub fn world_to_robot(robot_position: &RobotPosition) -> Self {
+        Self::robot_to_world(robot_position).inverse()
+    }
+
+    fn rotate(&self, coord: Coordinate) -> Coordinate {
+        coord.rotate(Orientation::North.direction_relative_to(self.orientation))
+    }
+
+    pub fn apply(&self, coord: Coordinate) -> Coordinate {
+        self.rotate(coord) + self.translation
+    }
+
+    // the transform that applies `other` first and then `self`
+    pub fn compose(&self, other: &Transform) -> Transform {
+        let orientation = Orientation::from_integer((self.orientation.integer_value() + other.orientation.integer_value()) % 4).unwrap();
+        Transform::new(self.apply(other.translation), orientation)
+    }
+
+    pub fn inverse(&self) -> Transform {
+        let orientation = Orientation::from_integer((4 - self.orientation.integer_value()) % 4).unwrap();
+        let inverse = Transform::new(Coordinate::new(0, 0), orientation);
+        Transform::new(-inverse.rotate(self.translation), orientation)
+    }
+
+    pub fn apply_to_robot(&self, robot_position: &RobotPosition) -> RobotPosition {
+        let pose = self.compose(&Self::robot_to_world(robot_position));
+        RobotPosition { position: pose.translation, orientation: pose.orientation }
+    }
+
+    // The origin is subtracted from the positions, so it turns with them but does not move, and the
+    // positions relative to it come out transformed like any other cell.
+    pub fn apply_to_enemies(&self, enemy_positions: &EnemyPositions) -> EnemyPositions {
+        let mut transformed = enemy_positions.clone();
+        for enemy in transformed.iter_mut() {
+            enemy.position = self.apply(enemy.position);
+        }
+        transformed.use_origin(self.rotate(enemy_positions.origin()));
+        transformed
+    }
+
+    // borders that end up outside of the arena are dropped
+    pub fn apply_to_borders(&self, borders: &Borders) -> Borders {
+        let mut transformed = Borders::new();
+        for coord in borders {
+            transformed.set_border(self.apply(coord));
+        }
+        transformed
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+#[cfg(test)]
+mod transform_tests {
+    use core::num::NonZero;
+
+    use crate::{direction::Direction, enemy_position::EnemyPosition, threat_map::ThreatMap};
+
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let robot_position = RobotPosition { position: Coordinate::new(1, 2), orientation: Orientation::East };
+        let to_world = Transform::robot_to_world(&robot_position);
+
+        for direction in [Direction::Front, Direction::Right, Direction::Back, Direction::Left] {
+            let local = Coordinate::new(0, 0).in_direction(direction, Orientation::North);
+            assert_eq!(to_world.apply(local), robot_position.in_direction(direction));
+        }
+
+        let to_robot = Transform::world_to_robot(&robot_position);
+        assert_eq!(to_robot.apply(Coordinate::new(1, 2)), Coordinate::new(0, 0));
+        assert_eq!(to_robot.apply(Coordinate::new(4, 2)), Coordinate::new(0, -3));
+        assert_eq!(to_robot.compose(&to_world), Transform::identity());
+        assert_eq!(to_world.compose(&to_robot), Transform::identity());
+    }
+
+    #[test]
+    fn test2() {
+        let a = Transform::new(Coordinate::new(1, -2), Orientation::West);
+        let b = Transform::new(Coordinate::new(-3, 0), Orientation::South);
+        let c = Coordinate::new(2, 1);
+
+        assert_eq!(a.compose(&b).apply(c), a.apply(b.apply(c)));
+        assert_eq!(a.compose(&b).inverse().apply(a.apply(b.apply(c))), c);
+        assert_eq!(b.inverse().inverse(), b);
+
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let moved = a.apply_to_robot(&robot_position);
+        assert_eq!(moved.position, Coordinate::new(1, -2));
+        assert_eq!(moved.orientation, Orientation::West);
+    }
+
+    #[test]
+    fn test3() {
+        // an enemy seen three cells in front of a robot facing south from (0, -2)
+        let robot_position = RobotPosition { position: Coordinate::new(0, -2), orientation: Orientation::South };
+        let mut seen = EnemyPositions::new();
+        seen.push(EnemyPosition::new(NonZero::new(1).unwrap(), Coordinate::new(0, -3)));
+
+        let world = Transform::robot_to_world(&robot_position).apply_to_enemies(&seen);
+        assert_eq!(world.get_position_of(NonZero::new(1).unwrap()), Some(Coordinate::new(0, 1)));
+
+        // positions relative to an origin are transformed as well
+        let mut relative = seen.clone();
+        relative.use_origin(Coordinate::new(1, -1));
+        let to_world = Transform::robot_to_world(&robot_position);
+        let expected = to_world.apply(relative.get_position_of(NonZero::new(1).unwrap()).unwrap());
+        assert_eq!(to_world.apply_to_enemies(&relative).get_position_of(NonZero::new(1).unwrap()), Some(expected));
+
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(0, -1));
+        borders.set_border(Coordinate::new(0, -4));
+        let world_borders = Transform::robot_to_world(&robot_position).apply_to_borders(&borders);
+        assert!(world_borders.is_border(Coordinate::new(0, -1)));
+        assert!(world_borders.is_border(Coordinate::new(0, 2)));
+        assert_eq!(world_borders.count(), 2);
+
+        let mut map: ThreatMap = ThreatMap::new();
+        map.calculate(&[world.get_position_of(NonZero::new(1).unwrap()).unwrap()]);
+        assert_eq!(map.at(robot_position.in_direction(Direction::Front)), 2);
+    }
+}
@@ -0,0 +1,283 @@
+use core::fmt::{self, Write};
+
+use crate::{borders::Borders, budget::NodeBudget, coordinates::Coordinate, direction::Direction, enemy_position_prediction::EnemyPositionPrediction, evaluation::{Evaluation, Scene, Term, TERMS}, greedy_next_move::GreedyPlanner, lookahead_next_move::lookahead_next_move_with_evaluation, metric::Metric, orientation::Orientation, rng::Rng, robot_position::{MoveModel, RobotPosition}, simulator::{SimulatedBehaviour, Simulator}, threat_map::ThreatMap, MAX_NUM_ENEMIES, N};
+
+const STEPS: [Coordinate; 5] = [Coordinate { x: 0, y: 0 }, Coordinate { x: 0, y: -1 }, Coordinate { x: 1, y: 0 }, Coordinate { x: 0, y: 1 }, Coordinate { x: -1, y: 0 }];
+// enemies start at least this far from the robot
+const MIN_START_DISTANCE: i32 = 4;
+
+// A random arena: a few borders, the robot and two to four enemies, one in three of them a pursuer.
+pub fn scenario(seed: u64) -> Simulator {
+    let mut rng = Rng::new(seed);
+    let random_cell = |rng: &mut Rng| Coordinate::from_index(rng.below((N * N) as u32) as usize).unwrap();
+
+    let mut borders = Borders::new();
+    for _ in 0..rng.below(7) {
+        borders.set_border(random_cell(&mut rng));
+    }
+    let robot = loop {
+        let cell = random_cell(&mut rng);
+        if !borders.is_border(cell) {
+            break cell;
+        }
+    };
+    let orientation = Orientation::from_integer(rng.below(4) as i32).unwrap();
+
+    let mut simulator = Simulator::new(RobotPosition { position: robot, orientation }, borders, rng.next_u32() as u64);
+    let enemy_count = 2 + rng.below(3);
+    let mut added = 0;
+    while added < enemy_count {
+        let cell = random_cell(&mut rng);
+        if borders.is_border(cell) || Metric::Manhattan.distance(cell, robot) < MIN_START_DISTANCE {
+            continue;
+        }
+        let behaviour = if rng.below(3) == 0 { SimulatedBehaviour::Pursuer } else { SimulatedBehaviour::Wanderer };
+        simulator.add_enemy(cell, STEPS[rng.below(5) as usize], behaviour);
+        added += 1;
+    }
+    simulator
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TunedPlanner {
+    Greedy,
+    // lookahead with this many nodes per tick
+    Lookahead(u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TuningSetup {
+    pub first_seed: u64,
+    pub scenarios: u32,
+    pub max_ticks: u32,
+    pub planner: TunedPlanner,
+}
+
+impl TuningSetup {
+    pub fn new() -> Self {
+        TuningSetup { first_seed: 1, scenarios: 32, max_ticks: 60, planner: TunedPlanner::Greedy }
+    }
+}
+
+impl Default for TuningSetup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// How long the robot lasted over a batch of scenarios.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SurvivalStats {
+    pub runs: u32,
+    pub total_ticks: u32,
+    pub min_ticks: u32,
+    // runs that lasted until the end
+    pub survived: u32,
+}
+
+impl SurvivalStats {
+    pub fn new() -> Self {
+        SurvivalStats { runs: 0, total_ticks: 0, min_ticks: u32::MAX, survived: 0 }
+    }
+
+    pub fn add(&mut self, ticks: u32, max_ticks: u32) {
+        self.runs += 1;
+        self.total_ticks += ticks;
+        self.min_ticks = self.min_ticks.min(ticks);
+        self.survived += (ticks >= max_ticks) as u32;
+    }
+
+    pub fn mean_ticks(&self) -> u32 {
+        self.total_ticks.checked_div(self.runs).unwrap_or(0)
+    }
+}
+
+impl Default for SurvivalStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn prediction_of(simulator: &Simulator) -> EnemyPositionPrediction {
+    let mut prediction = EnemyPositionPrediction::new(&simulator.enemy_positions(), simulator.previous_enemy_positions(), *simulator.borders());
+    prediction.use_pursuit_target(simulator.robot_position().position);
+    prediction
+}
+
+// The number of ticks the planner keeps the robot alive in `simulator`. The simulator carries the
+// moves out the way the planners score them, as sidesteps, and the planners get a threat map of the
+// enemies around the borders so that every term can be tuned.
+pub fn play(simulator: &mut Simulator, evaluation: &Evaluation, planner: TunedPlanner, max_ticks: u32) -> u32 {
+    simulator.use_move_model(MoveModel::Sidestep);
+    let mut greedy = GreedyPlanner::new();
+    greedy.use_evaluation(*evaluation);
+    let mut threat_map = ThreatMap::new();
+    simulator.run(max_ticks, |s| -> Option<Direction> {
+        let prediction = prediction_of(s);
+        let mut enemies = [Coordinate::new(0, 0); MAX_NUM_ENEMIES];
+        let count = enemies.iter_mut().zip(prediction.positions()).map(|(slot, c)| *slot = c).count();
+        threat_map.calculate_with_borders(&enemies[..count], s.borders());
+        let scene = Scene::new(&prediction, s.borders()).with_threat_map(&threat_map);
+        match planner {
+            TunedPlanner::Greedy => greedy.next_move_in(s.robot_position(), &scene),
+            TunedPlanner::Lookahead(nodes) => lookahead_next_move_with_evaluation(s.robot_position(), &scene, evaluation, &mut NodeBudget::new(nodes)),
+        }
+    })
+}
+
+pub fn measure(setup: &TuningSetup, evaluation: &Evaluation) -> SurvivalStats {
+    let mut stats = SurvivalStats::new();
+    for seed in setup.first_seed..setup.first_seed + setup.scenarios as u64 {
+        let ticks = play(&mut scenario(seed), evaluation, setup.planner, setup.max_ticks);
+        stats.add(ticks, setup.max_ticks);
+    }
+    stats
+}
+
+// Coordinate descent on the weights: every round tries each weight `step` higher and lower and
+// keeps what makes the robot survive longer over the scenarios of `setup`. The step is halved
+// after a round without improvement. `report` is called after every round.
+pub fn tune<F: FnMut(u32, &Evaluation, &SurvivalStats)>(setup: &TuningSetup, start: Evaluation, rounds: u32, step: i32, mut report: F) -> (Evaluation, SurvivalStats) {
+    let mut best = start;
+    let mut best_stats = measure(setup, &best);
+    let mut step = step;
+
+    for round in 0..rounds {
+        if step == 0 {
+            break;
+        }
+        let mut improved = false;
+        for term in TERMS {
+            for delta in [step, -step] {
+                let mut candidate = best;
+                candidate.use_weight(term, best.weight(term) + delta);
+                let stats = measure(setup, &candidate);
+                if stats.total_ticks > best_stats.total_ticks {
+                    best = candidate;
+                    best_stats = stats;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            step /= 2;
+        }
+        report(round, &best, &best_stats);
+    }
+    (best, best_stats)
+}
+
+// One `Name = weight` line per term.
+pub fn write_config<W: Write>(evaluation: &Evaluation, out: &mut W) -> fmt::Result {
+    for term in TERMS {
+        writeln!(out, "{} = {}", term.name(), evaluation.weight(term))?;
+    }
+    Ok(())
+}
+
+// Reads what `write_config` writes, terms that are left out weigh 0 and lines starting with #
+// are ignored. None if a line cannot be read.
+pub fn parse_config(text: &str) -> Option<Evaluation> {
+    let mut evaluation = Evaluation::empty();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let (name, weight) = line.split_once('=')?;
+        evaluation.use_weight(Term::from_name(name.trim())?, weight.trim().parse().ok()?);
+    }
+    Some(evaluation)
+}
+
+// The evaluation as a Rust const named `name`, to paste into the robot's code.
+pub fn write_rust_const<W: Write>(evaluation: &Evaluation, name: &str, out: &mut W) -> fmt::Result {
+    write!(out, "// ")?;
+    for (i, term) in TERMS.iter().enumerate() {
+        write!(out, "{}{}", if i == 0 { "" } else { ", " }, term.name())?;
+    }
+    writeln!(out)?;
+    write!(out, "pub const {}: Evaluation = Evaluation::from_weights({:?});", name, evaluation.weights())?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tuning_tests {
+    use super::*;
+
+    struct Buffer {
+        bytes: [u8; 512],
+        length: usize,
+    }
+
+    impl Buffer {
+        fn new() -> Self {
+            Buffer { bytes: [0; 512], length: 0 }
+        }
+
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.bytes[..self.length]).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.length + s.len();
+            if end > self.bytes.len() {
+                return Err(fmt::Error);
+            }
+            self.bytes[self.length..end].copy_from_slice(s.as_bytes());
+            self.length = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test1() {
+        for seed in 1..20 {
+            let a = scenario(seed);
+            let b = scenario(seed);
+            assert_eq!(a.robot_position().position, b.robot_position().position);
+            assert_eq!(a.enemy_positions().len(), b.enemy_positions().len());
+            assert!((2..=4).contains(&a.enemy_positions().len()));
+            assert!(!a.borders().is_border(a.robot_position().position));
+            assert!(!a.is_caught());
+        }
+
+        let setup = TuningSetup { scenarios: 4, max_ticks: 20, ..TuningSetup::new() };
+        let stats = measure(&setup, &Evaluation::new());
+        assert_eq!(stats.runs, 4);
+        assert!(stats.min_ticks <= stats.mean_ticks() && stats.mean_ticks() <= 20);
+        assert_eq!(stats, measure(&setup, &Evaluation::new()));
+
+        // the planners see a threat map, so weighing the threat changes how they play
+        let mut threat = Evaluation::empty();
+        threat.use_weight(Term::Threat, 1);
+        assert_ne!(measure(&setup, &threat), measure(&setup, &Evaluation::empty()));
+    }
+
+    #[test]
+    fn test2() {
+        let mut evaluation = Evaluation::new();
+        evaluation.use_weight(Term::CentreDistance, -2);
+
+        let mut buffer = Buffer::new();
+        write_config(&evaluation, &mut buffer).unwrap();
+        assert!(buffer.as_str().contains("CentreDistance = -2"));
+        assert_eq!(parse_config(buffer.as_str()), Some(evaluation));
+        assert_eq!(parse_config("# tuned\nMinEnemyDistance = 1\n\nCentreDistance = -2\n"), Some(evaluation));
+        assert_eq!(parse_config("Speed = 3"), None);
+        assert_eq!(parse_config("MinEnemyDistance = fast"), None);
+
+        let mut buffer = Buffer::new();
+        write_rust_const(&evaluation, "TUNED", &mut buffer).unwrap();
+        assert!(buffer.as_str().contains("pub const TUNED: Evaluation = Evaluation::from_weights([1, 0, 0, 0, -2, 0]);"));
+    }
+
+    #[test]
+    fn test3() {
+        let setup = TuningSetup { scenarios: 6, max_ticks: 30, ..TuningSetup::new() };
+        let start = measure(&setup, &Evaluation::new());
+        let mut rounds = 0;
+        let (evaluation, stats) = tune(&setup, Evaluation::new(), 2, 2, |_, _, _| rounds += 1);
+        assert_eq!(rounds, 2);
+        assert!(stats.total_ticks >= start.total_ticks);
+        assert_eq!(stats, measure(&setup, &evaluation));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{borders::Borders, coordinates::Coordinate, direction::Direction, line::Line, orientation::Orientation, robot_position::RobotPosition, N};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SensorCone {
+    Full,
+    // a cell `forward` cells in front of the robot is inside the cone up to `lateral` cells to either side,
+    // e.g. lateral = forward = 1 is a 90 degree cone
+    Cone { lateral: i32, forward: i32 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SensorModel {
+    pub cone: SensorCone,
+    // euclidean range in cells
+    pub range: i32,
+}
+
+impl SensorModel {
+    pub fn new(cone: SensorCone, range: i32) -> Self {
+        SensorModel { cone, range }
+    }
+
+    fn in_cone(&self, robot_position: &RobotPosition, coord: Coordinate) -> bool {
+        let relative = (coord - robot_position.position).orientate_north(robot_position.orientation);
+        let (forward, lateral) = (-(relative.y as i32), (relative.x as i32).abs());
+        if forward * forward + lateral * lateral > self.range * self.range {
+            return false;
+        }
+        match self.cone {
+            SensorCone::Full => true,
+            SensorCone::Cone { lateral: cone_lateral, forward: cone_forward } => forward >= 0 && lateral * cone_forward <= forward * cone_lateral,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VisibleCells {
+    visible: [bool; N * N]
+}
+
+impl VisibleCells {
+    // A cell is visible when it is inside the sensor cone and the line from the robot to it does not
+    // pass through a border. Borders themselves can be seen, but nothing behind them.
+    pub fn compute(robot_position: &RobotPosition, sensor: &SensorModel, borders: &Borders) -> Self {
+        let mut visible = [false; N * N];
+        for (index, cell) in visible.iter_mut().enumerate() {
+            let coord = Coordinate::from_index(index).unwrap();
+            if coord == robot_position.position {
+                *cell = true;
+                continue;
+            }
+            if !sensor.in_cone(robot_position, coord) {
+                continue;
+            }
+            *cell = Line::new(robot_position.position, coord)
+                .filter(|&c| c != robot_position.position && c != coord)
+                .all(|c| !borders.is_border(c));
+        }
+        VisibleCells { visible }
+    }
+
+    pub fn is_visible(&self, coord: Coordinate) -> bool {
+        match coord.to_index() {
+            Some(i) => self.visible[i],
+            None => false
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.visible.iter().filter(|&&v| v).count()
+    }
+
+    // the number of free neighbours of `coord` from which an enemy could step onto it unseen
+    pub fn hidden_approaches(&self, coord: Coordinate, borders: &Borders) -> usize {
+        [Direction::Front, Direction::Right, Direction::Back, Direction::Left].into_iter()
+            .map(|d| coord.in_direction(d, Orientation::North))
+            .filter(|&c| c.to_index().is_some() && !borders.is_border(c) && !self.is_visible(c))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod visibility_tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::East };
+        let sensor = SensorModel::new(SensorCone::Cone { lateral: 1, forward: 1 }, 3);
+        let visible = VisibleCells::compute(&robot_position, &sensor, &Borders::new());
+
+        assert!(visible.is_visible(Coordinate::new(0, 0)));
+        assert!(visible.is_visible(Coordinate::new(3, 0)));
+        assert!(visible.is_visible(Coordinate::new(2, 2)));
+        assert!(visible.is_visible(Coordinate::new(2, -2)));
+        assert!(!visible.is_visible(Coordinate::new(4, 0)));
+        assert!(!visible.is_visible(Coordinate::new(1, 2)));
+        assert!(!visible.is_visible(Coordinate::new(-1, 0)));
+        assert!(!visible.is_visible(Coordinate::new(0, 1)));
+
+        let visible = VisibleCells::compute(&robot_position, &SensorModel::new(SensorCone::Full, 1), &Borders::new());
+        assert_eq!(visible.count(), 5);
+        assert_eq!(visible.hidden_approaches(Coordinate::new(1, 0), &Borders::new()), 3);
+    }
+
+    #[test]
+    fn test2() {
+        let robot_position = RobotPosition { position: Coordinate::new(0, 0), orientation: Orientation::North };
+        let mut borders = Borders::new();
+        borders.set_border(Coordinate::new(0, -2));
+        let visible = VisibleCells::compute(&robot_position, &SensorModel::new(SensorCone::Full, 8), &borders);
+
+        assert!(visible.is_visible(Coordinate::new(0, -1)));
+        assert!(visible.is_visible(Coordinate::new(0, -2)));
+        assert!(!visible.is_visible(Coordinate::new(0, -3)));
+        assert!(!visible.is_visible(Coordinate::new(0, -4)));
+        assert!(visible.is_visible(Coordinate::new(1, -4)));
+        assert!(visible.is_visible(Coordinate::new(0, 4)));
+    }
+}
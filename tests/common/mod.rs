@@ -0,0 +1,167 @@
+// Random arenas and slow but obviously correct reference implementations for the property and
+// differential tests.
+
+#![allow(dead_code)]
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::num::NonZero;
+
+use threat_map::{
+    borders::Borders,
+    coordinates::Coordinate,
+    enemy_position::{EnemyPosition, EnemyPositions},
+    metric::Metric,
+    orientation::Orientation,
+    rng::Rng,
+    robot_position::RobotPosition,
+    MAX_NUM_ENEMIES, N,
+};
+
+pub const CASES: u64 = 200;
+
+// Runs `property` once for every seed, with a generator seeded by it. The seed is in the panic
+// message of a failing case, so that it can be replayed.
+pub fn for_all<F: FnMut(u64, &mut Rng)>(cases: u64, mut property: F) {
+    for seed in 1..=cases {
+        let mut rng = Rng::new(seed);
+        property(seed, &mut rng);
+    }
+}
+
+pub fn all_cells() -> impl Iterator<Item = Coordinate> {
+    (0..N * N).map(|i| Coordinate::from_index(i).unwrap())
+}
+
+pub fn random_cell(rng: &mut Rng) -> Coordinate {
+    Coordinate::from_index(rng.below((N * N) as u32) as usize).unwrap()
+}
+
+// up to a third of the arena walled off
+pub fn random_borders(rng: &mut Rng) -> Borders {
+    let mut borders = Borders::new();
+    for _ in 0..rng.below((N * N / 3) as u32) {
+        borders.set_border(random_cell(rng));
+    }
+    borders
+}
+
+pub fn random_free_cell(rng: &mut Rng, borders: &Borders) -> Option<Coordinate> {
+    (0..100).map(|_| random_cell(rng)).find(|&c| !borders.is_border(c))
+}
+
+pub fn random_orientation(rng: &mut Rng) -> Orientation {
+    Orientation::from_integer(rng.below(4) as i32).unwrap()
+}
+
+pub fn random_robot(rng: &mut Rng, borders: &Borders) -> Option<RobotPosition> {
+    let position = random_free_cell(rng, borders)?;
+    Some(RobotPosition { position, orientation: random_orientation(rng) })
+}
+
+// one to MAX_NUM_ENEMIES enemies on free cells
+pub fn random_enemies(rng: &mut Rng, borders: &Borders) -> EnemyPositions {
+    let mut enemies = EnemyPositions::new();
+    let count = 1 + rng.below(MAX_NUM_ENEMIES as u32);
+    for id in 1..=count as u64 {
+        if let Some(cell) = random_free_cell(rng, borders) {
+            enemies.push(EnemyPosition::new(NonZero::new(id).unwrap(), cell));
+        }
+    }
+    enemies
+}
+
+pub fn positions(enemies: &EnemyPositions) -> Vec<Coordinate> {
+    enemies.iter().map(|e| e.position).collect()
+}
+
+// Shortest paths from every source at once through cells that are not borders, with the steps of
+// `metric`: plain breadth first search for unit steps, Dijkstra with diagonal steps of sqrt(2) in
+// millionths of a cell for octile. Squared Euclidean has no path form, a cell gets the squared
+// straight distance to the closest source that can get there. Border cells are 0 like in the
+// threat map, unreachable cells i32::MAX.
+pub fn reference_distances(sources: &[Coordinate], borders: &Borders, metric: Metric) -> Vec<i32> {
+    let mut distances = vec![i32::MAX; N * N];
+    if metric == Metric::SquaredEuclidean {
+        for &source in sources {
+            let reached = reference_distances(&[source], borders, Metric::Chebyshev);
+            for cell in all_cells().filter(|c| reached[c.to_index().unwrap()] != i32::MAX) {
+                let (dx, dy) = (cell.x as i32 - source.x as i32, cell.y as i32 - source.y as i32);
+                let index = cell.to_index().unwrap();
+                distances[index] = distances[index].min(dx * dx + dy * dy);
+            }
+        }
+    } else {
+        let (straight, diagonal) = match metric {
+            Metric::Octile => (1_000_000, Some(1_414_214)),
+            Metric::Chebyshev => (1, Some(1)),
+            _ => (1, None),
+        };
+        let mut lengths = vec![i64::MAX; N * N];
+        let mut queue = BinaryHeap::new();
+        for &source in sources {
+            lengths[source.to_index().unwrap()] = 0;
+            queue.push(Reverse((0, source.to_index().unwrap())));
+        }
+        while let Some(Reverse((length, index))) = queue.pop() {
+            if length > lengths[index] {
+                continue;
+            }
+            let cell = Coordinate::from_index(index).unwrap();
+            for dx in -1i8..=1 {
+                for dy in -1i8..=1 {
+                    let step = match (dx, dy) {
+                        (0, 0) => continue,
+                        (0, _) | (_, 0) => straight,
+                        _ => match diagonal {
+                            Some(step) => step,
+                            None => continue,
+                        },
+                    };
+                    let next = Coordinate::new(cell.x + dx, cell.y + dy);
+                    let Some(next_index) = next.to_index() else { continue };
+                    if borders.is_border(next) || lengths[next_index] <= length + step {
+                        continue;
+                    }
+                    lengths[next_index] = length + step;
+                    queue.push(Reverse((length + step, next_index)));
+                }
+            }
+        }
+        for (distance, &length) in distances.iter_mut().zip(lengths.iter()) {
+            if length != i64::MAX {
+                *distance = ((length + straight / 2) / straight) as i32;
+            }
+        }
+    }
+    for cell in borders.iter() {
+        distances[cell.to_index().unwrap()] = 0;
+    }
+    distances
+}
+
+// the number of four-connected groups of border cells, by flood filling a plain array
+pub fn reference_component_count(borders: &Borders) -> usize {
+    let mut seen = [false; N * N];
+    let mut count = 0;
+    for start in all_cells().filter(|&c| borders.is_border(c)) {
+        if seen[start.to_index().unwrap()] {
+            continue;
+        }
+        count += 1;
+        let mut stack = vec![start];
+        seen[start.to_index().unwrap()] = true;
+        while let Some(cell) = stack.pop() {
+            for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                let next = Coordinate::new(cell.x + dx, cell.y + dy);
+                if let Some(index) = next.to_index() {
+                    if borders.is_border(next) && !seen[index] {
+                        seen[index] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+    }
+    count
+}
@@ -0,0 +1,161 @@
+// Optimised implementations against straightforward ones over random inputs.
+
+mod common;
+
+use std::num::NonZero;
+
+use common::*;
+use threat_map::{
+    borders::Borders,
+    coordinates::Coordinate,
+    enemy_position::{EnemyPosition, EnemyPositions},
+    incremental_threat_map::IncrementalThreatMap,
+    metric::Metric,
+    rng::Rng,
+    threat_map::{ticks_to_reach, ThreatMap},
+    MAX_NUM_ENEMIES, N,
+};
+
+#[test]
+fn incremental_threat_map_matches_recalculation() {
+    for_all(CASES, |seed, rng| {
+        let mut borders = random_borders(rng);
+        let metric = [Metric::Manhattan, Metric::Chebyshev, Metric::Octile][rng.below(3) as usize];
+        let mut incremental: IncrementalThreatMap = IncrementalThreatMap::new(borders);
+        incremental.use_metric(metric);
+        let mut next_id = 1;
+
+        for step in 0..30 {
+            match rng.below(4) {
+                0 if incremental.enemies().len() < MAX_NUM_ENEMIES => {
+                    if let Some(cell) = random_free_cell(rng, &borders) {
+                        incremental.add_enemy(EnemyPosition::new(NonZero::new(next_id).unwrap(), cell));
+                        next_id += 1;
+                    }
+                }
+                1 if !incremental.enemies().is_empty() => {
+                    let id = incremental.enemies().iter().nth(rng.below(incremental.enemies().len() as u32) as usize).unwrap().id;
+                    incremental.remove_enemy(id);
+                }
+                2 if !incremental.enemies().is_empty() => {
+                    let id = incremental.enemies().iter().nth(rng.below(incremental.enemies().len() as u32) as usize).unwrap().id;
+                    if let Some(cell) = random_free_cell(rng, &borders) {
+                        incremental.move_enemy(id, cell);
+                    }
+                }
+                _ => {
+                    let cell = random_cell(rng);
+                    if !incremental.enemies().iter().any(|e| e.position == cell) {
+                        borders.set_border(cell);
+                        incremental.add_border(cell);
+                    }
+                }
+            }
+
+            let mut expected: ThreatMap = ThreatMap::new();
+            expected.use_metric(metric);
+            expected.calculate_with_borders(&positions(incremental.enemies()), &borders);
+            for cell in all_cells() {
+                assert_eq!(incremental.threat_map().distance_at(cell), expected.distance_at(cell), "seed {seed} step {step} {:?} {:?}", metric, cell);
+            }
+        }
+    });
+}
+
+#[test]
+fn narrow_threat_cells_match_i32() {
+    for_all(CASES, |seed, rng| {
+        let borders = random_borders(rng);
+        let enemies = positions(&random_enemies(rng, &borders));
+        let mut wide: ThreatMap = ThreatMap::new();
+        let mut narrow: ThreatMap<u8> = ThreatMap::empty();
+        let mut medium: ThreatMap<u16> = ThreatMap::empty();
+        wide.calculate_with_borders(&enemies, &borders);
+        narrow.calculate_with_borders(&enemies, &borders);
+        medium.calculate_with_borders(&enemies, &borders);
+        for cell in all_cells() {
+            assert_eq!(narrow.distance_at(cell), wide.distance_at(cell), "seed {seed} {:?}", cell);
+            assert_eq!(medium.distance_at(cell), wide.distance_at(cell), "seed {seed} {:?}", cell);
+        }
+    });
+}
+
+#[test]
+fn ticks_to_reach_matches_search_per_enemy() {
+    for_all(CASES, |seed, rng| {
+        let borders = random_borders(rng);
+        let enemies: Vec<_> = positions(&random_enemies(rng, &borders)).into_iter().map(|c| (c, rng.below(4) as i32)).collect();
+        let mut map: ThreatMap = ThreatMap::new();
+        map.calculate_ticks_to_reach(&enemies, &borders);
+
+        let per_enemy: Vec<_> = enemies.iter().map(|&(c, _)| reference_distances(&[c], &borders, Metric::Manhattan)).collect();
+        for cell in all_cells().filter(|&c| !borders.is_border(c)) {
+            let index = cell.to_index().unwrap();
+            let expected = enemies.iter().zip(&per_enemy).map(|(&(_, speed), distances)| ticks_to_reach(distances[index], speed)).min().unwrap();
+            assert_eq!(map.distance_at(cell), expected, "seed {seed} {:?}", cell);
+        }
+    });
+}
+
+#[test]
+fn ticks_of_diagonal_movers_match_search_per_enemy() {
+    for_all(CASES, |seed, rng| {
+        let borders = random_borders(rng);
+        let current = random_enemies(rng, &borders);
+        // every enemy came one or two cells diagonally, from wherever that was
+        let mut previous = EnemyPositions::new();
+        let mut lengths = Vec::new();
+        for enemy in &current {
+            let length = 1 + rng.below(2) as i8;
+            let sign = |rng: &mut Rng| if rng.below(2) == 0 { 1 } else { -1 };
+            let step = Coordinate::new(sign(rng) * length, sign(rng) * length);
+            previous.push(EnemyPosition::new(enemy.id, enemy.position - step));
+            lengths.push(length as i32);
+        }
+
+        // a diagonal step covers both axes in Manhattan distance, only one in Chebyshev
+        for (metric, cells_per_step) in [(Metric::Manhattan, 2), (Metric::Chebyshev, 1)] {
+            let mut map: ThreatMap = ThreatMap::new();
+            map.use_metric(metric);
+            map.calculate_ticks_with_previous_location(&current, &previous, &borders);
+
+            let per_enemy: Vec<_> = current.iter().map(|e| reference_distances(&[e.position], &borders, metric)).collect();
+            for cell in all_cells().filter(|&c| !borders.is_border(c)) {
+                let index = cell.to_index().unwrap();
+                let expected = lengths.iter().zip(&per_enemy).map(|(&length, distances)| ticks_to_reach(distances[index], cells_per_step * length)).min().unwrap();
+                assert_eq!(map.distance_at(cell), expected, "seed {seed} {:?} {:?}", metric, cell);
+            }
+        }
+    });
+}
+
+#[test]
+fn borders_match_a_plain_array() {
+    for_all(CASES, |seed, rng| {
+        let mut borders = Borders::new();
+        let mut cells = [false; N * N];
+        for _ in 0..60 {
+            let cell = random_cell(rng);
+            let index = cell.to_index().unwrap();
+            match rng.below(3) {
+                0 => {
+                    borders.set_border(cell);
+                    cells[index] = true;
+                }
+                1 => {
+                    borders.clear_border(cell);
+                    cells[index] = false;
+                }
+                _ => {
+                    borders.toggle(cell);
+                    cells[index] = !cells[index];
+                }
+            }
+        }
+        assert_eq!(borders.count(), cells.iter().filter(|&&b| b).count(), "seed {seed}");
+        let listed: Vec<_> = borders.iter().collect();
+        let expected: Vec<_> = all_cells().filter(|c| cells[c.to_index().unwrap()]).collect();
+        assert_eq!(listed, expected, "seed {seed}");
+        assert_eq!(borders.connected_components().count(), reference_component_count(&borders), "seed {seed}");
+    });
+}
@@ -0,0 +1,175 @@
+// Invariants checked over many random arenas.
+
+mod common;
+
+use common::*;
+use threat_map::{
+    budget::NodeBudget,
+    coordinates::Coordinate,
+    direction::Direction,
+    enemy_position::EnemyPositions,
+    enemy_position_prediction::EnemyPositionPrediction,
+    greedy_next_move::{greedy_next_move, GreedyPlanner, TieBreak},
+    lookahead_next_move::lookahead_next_move,
+    mcts::{Mcts, MctsConfig},
+    metric::Metric,
+    orientation::Orientation,
+    robot_position::{MoveModel, RobotPosition},
+    team::TeamPlanner,
+    threat_map::ThreatMap,
+    transform::Transform,
+    N,
+};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Front, Direction::Right, Direction::Back, Direction::Left];
+const ORIENTATIONS: [Orientation; 4] = [Orientation::North, Orientation::East, Orientation::South, Orientation::West];
+
+#[test]
+fn index_round_trip() {
+    for index in 0..N * N {
+        let coord = Coordinate::from_index(index).unwrap();
+        assert_eq!(coord.to_index(), Some(index));
+    }
+    assert_eq!(Coordinate::from_index(N * N), None);
+
+    let bound = (N / 2) as i8;
+    for x in -bound - 2..=bound + 2 {
+        for y in -bound - 2..=bound + 2 {
+            let inside = x.abs() <= bound && y.abs() <= bound;
+            let coord = Coordinate::new(x, y);
+            assert_eq!(coord.to_index().is_some(), inside, "{:?}", coord);
+            if let Some(index) = coord.to_index() {
+                assert_eq!(Coordinate::from_index(index), Some(coord));
+            }
+        }
+    }
+}
+
+#[test]
+fn rotating_four_times_is_identity() {
+    for_all(CASES, |seed, rng| {
+        let coord = Coordinate::new(rng.below(41) as i8 - 20, rng.below(41) as i8 - 20);
+        for direction in DIRECTIONS {
+            let rotated = (0..4).fold(coord, |c, _| c.rotate(direction));
+            assert_eq!(rotated, coord, "seed {seed}");
+        }
+        assert_eq!(coord.rotate(Direction::Right).rotate(Direction::Left), coord, "seed {seed}");
+        assert_eq!(coord.rotate(Direction::Back).rotate(Direction::Back), coord, "seed {seed}");
+    });
+}
+
+#[test]
+fn front_then_back_is_identity() {
+    for position in all_cells() {
+        for orientation in ORIENTATIONS {
+            let start = RobotPosition { position, orientation };
+            for (there, back) in [(Direction::Front, Direction::Back), (Direction::Back, Direction::Front), (Direction::Right, Direction::Left), (Direction::Left, Direction::Right)] {
+                let mut robot = start;
+                robot.take_step(there);
+                robot.take_step(back);
+                assert_eq!((robot.position, robot.orientation), (start.position, start.orientation));
+            }
+            // four turns the same way bring the robot back to its heading
+            let mut robot = start;
+            for _ in 0..4 {
+                robot.take_step(Direction::Right);
+            }
+            assert_eq!(robot.orientation, orientation);
+        }
+    }
+}
+
+#[test]
+fn transform_inverse_is_identity() {
+    for_all(CASES, |seed, rng| {
+        let robot = RobotPosition { position: random_cell(rng), orientation: random_orientation(rng) };
+        let transform = Transform::robot_to_world(&robot);
+        let coord = random_cell(rng);
+        assert_eq!(transform.inverse().apply(transform.apply(coord)), coord, "seed {seed}");
+        assert_eq!(transform.compose(&transform.inverse()), Transform::identity(), "seed {seed}");
+    });
+}
+
+#[test]
+fn threat_map_matches_brute_force() {
+    for_all(CASES, |seed, rng| {
+        let enemies = positions(&random_enemies(rng, &Default::default()));
+        for (metric, distance) in [
+            (Metric::Manhattan, (|dx: i32, dy: i32| dx + dy) as fn(i32, i32) -> i32),
+            (Metric::Chebyshev, |dx, dy| dx.max(dy)),
+            (Metric::SquaredEuclidean, |dx, dy| dx * dx + dy * dy),
+        ] {
+            let mut map: ThreatMap = ThreatMap::new();
+            map.use_metric(metric);
+            map.calculate(&enemies);
+            for cell in all_cells() {
+                let expected = enemies.iter().map(|e| distance((e.x as i32 - cell.x as i32).abs(), (e.y as i32 - cell.y as i32).abs())).min().unwrap();
+                assert_eq!(map.distance_at(cell), expected, "seed {seed} {:?} {:?}", metric, cell);
+            }
+        }
+    });
+}
+
+#[test]
+fn threat_map_with_borders_matches_shortest_paths() {
+    for_all(CASES, |seed, rng| {
+        let borders = random_borders(rng);
+        let enemies = positions(&random_enemies(rng, &borders));
+        for metric in [Metric::Manhattan, Metric::Chebyshev, Metric::Octile, Metric::SquaredEuclidean] {
+            let mut map: ThreatMap = ThreatMap::new();
+            map.use_metric(metric);
+            map.calculate_with_borders(&enemies, &borders);
+            let expected = reference_distances(&enemies, &borders, metric);
+            for cell in all_cells() {
+                assert_eq!(map.distance_at(cell), expected[cell.to_index().unwrap()], "seed {seed} {:?} {:?}", metric, cell);
+            }
+        }
+    });
+}
+
+// Where a move takes the robot. Every escape planner scores its moves as sidesteps.
+fn target(robot: &RobotPosition, direction: Direction) -> Coordinate {
+    let mut next = *robot;
+    next.apply(direction, MoveModel::Sidestep);
+    next.position
+}
+
+#[test]
+fn planners_never_enter_a_border() {
+    for_all(CASES, |seed, rng| {
+        let borders = random_borders(rng);
+        let Some(robot) = random_robot(rng, &borders) else { return };
+        let enemies = random_enemies(rng, &borders);
+        let prediction = EnemyPositionPrediction::new(&enemies, &EnemyPositions::new(), borders);
+
+        if let Some(direction) = greedy_next_move(&robot, &prediction, &borders) {
+            assert!(!borders.is_border(target(&robot, direction)), "seed {seed} greedy {:?}", direction);
+        }
+        for tie_break in [TieBreak::MoveOrder, TieBreak::PreferFront, TieBreak::KeepHeading, TieBreak::TowardCentre, TieBreak::Random] {
+            let mut planner = GreedyPlanner::new();
+            planner.use_tie_break(tie_break);
+            planner.use_seed(seed);
+            if let Some(direction) = planner.next_move(&robot, &prediction, &borders) {
+                assert!(!borders.is_border(target(&robot, direction)), "seed {seed} {:?} {:?}", tie_break, direction);
+            }
+        }
+
+        // the team planner sidesteps too, for one robot as for many
+        let mut threat_map: ThreatMap = ThreatMap::new();
+        threat_map.calculate_with_borders(&positions(&enemies), &borders);
+        if let Some(direction) = TeamPlanner::new().next_moves(&[robot], &prediction, &threat_map, &borders)[0] {
+            let target = target(&robot, direction);
+            assert!(!borders.is_border(target) && target.to_index().is_some(), "seed {seed} team {:?}", direction);
+        }
+
+        // the searching planners do not leave the arena either
+        let searched = [
+            lookahead_next_move(&robot, &prediction, &borders, &mut NodeBudget::new(500)),
+            Mcts::new(MctsConfig { iterations: 50, seed, ..MctsConfig::new() }).next_move(&robot, &prediction, &borders),
+        ];
+        for direction in searched.into_iter().flatten() {
+            let target = target(&robot, direction);
+            assert!(!borders.is_border(target) && target.to_index().is_some(), "seed {seed} {:?}", direction);
+        }
+    });
+}